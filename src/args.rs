@@ -1,42 +1,400 @@
-use crate::error::Error;
-use crate::timestamp::Timestamp;
+use ttrim::timestamp::ChapterRef;
+use ttrim::{BlackOptions, SilenceOptions, TrimJob};
+use ttrim::{EncodeOptions, Error, Snap, TimeRange, Timestamp, TrimMode};
 
-use clap::Parser;
+use clap::{ArgGroup, Parser, Subcommand};
+use std::collections::HashSet;
 use std::path;
 
 #[derive(Parser, Debug, Default)]
-#[clap(author = "Dylan Morrison", version)]
+#[clap(
+    author = "Dylan Morrison",
+    version,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 /// ttrim - Trim video files directly in the terminal.
 pub struct Args {
-    /// Path to the video file to be trimmed.
-    pub target_file: path::PathBuf,
-    #[clap(short, long = "start")]
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+    #[clap(required = true)]
+    /// Paths to the video files to be trimmed, which can include glob patterns such as `*.mp4`.
+    /// Every file is trimmed the same way.
+    pub target_files: Vec<path::PathBuf>,
+    #[clap(short, long = "start", allow_hyphen_values = true, conflicts_with_all = ["keep", "cut"])]
     /// The desired starting point of the trimmed video.
-    /// Can be provided as a timestamp in the format `HH:MM:SS`, `MM:SS`, a percentage `DD%` or as a number of seconds.
+    /// Can be provided as a timestamp in the format `HH:MM:SS`, `MM:SS`, a percentage `DD.D%` or as a number of seconds.
+    /// Seconds can include milliseconds, e.g. `01:23.456`, `12.5` or `00:01:23,456`, or use units such as `1h2m3s`, `90s` or `1500ms`.
+    /// A frame number can be provided as `f1234` or `1234f`, or a SMPTE timecode as `HH:MM:SS:FF` or `HH:MM:SS;FF` for drop-frame.
+    /// Prefix with `-` to count back from the end of the video, e.g. `-30`.
     pub start_timestamp: Option<String>,
-    #[clap(short, long = "end")]
+    #[clap(
+        short,
+        long = "end",
+        allow_hyphen_values = true,
+        conflicts_with_all = ["keep", "cut", "duration"]
+    )]
     /// The desired end point of the trimmed video.
-    /// Can be provided as a timestamp in the format `HH:MM:SS`, `MM:SS`, a percentage `DD%` or as a number of seconds.
+    /// Can be provided as a timestamp in the format `HH:MM:SS`, `MM:SS`, a percentage `DD.D%` or as a number of seconds.
+    /// Seconds can include milliseconds, e.g. `01:23.456`, `12.5` or `00:01:23,456`, or use units such as `1h2m3s`, `90s` or `1500ms`.
+    /// A frame number can be provided as `f1234` or `1234f`, or a SMPTE timecode as `HH:MM:SS:FF` or `HH:MM:SS;FF` for drop-frame.
+    /// Prefix with `+` to make it relative to the start, e.g. `+45` or `+1:30`.
+    /// Prefix with `-` to count back from the end of the video, e.g. `-0:10`.
     pub end_timestamp: Option<String>,
+    #[clap(short, long, conflicts_with_all = ["keep", "cut"])]
+    /// The length of the trimmed video, in any of the formats accepted by `--end`.
+    /// Equivalent to a relative end, e.g. `-d 45` is the same as `-e +45`.
+    pub duration: Option<String>,
+    #[clap(
+        long,
+        conflicts_with_all = ["start_timestamp", "end_timestamp", "duration", "from_chapter", "to_chapter", "keep", "cut"]
+    )]
+    /// Trim the video to a single chapter, provided as its number starting from 1 or its title.
+    pub chapter: Option<String>,
+    #[clap(long, conflicts_with_all = ["start_timestamp", "keep", "cut"])]
+    /// Start the trimmed video at the beginning of a chapter, provided as its number starting from 1 or its title.
+    pub from_chapter: Option<String>,
+    #[clap(long, conflicts_with_all = ["end_timestamp", "duration", "keep", "cut"])]
+    /// End the trimmed video at the end of a chapter, provided as its number starting from 1 or its title.
+    pub to_chapter: Option<String>,
+    #[clap(short, long, value_delimiter = ',', allow_hyphen_values = true)]
+    /// Ranges of the video to keep, which will be joined together into a single output file.
+    /// Provided as a comma separated list of `START-END` pairs, e.g. `10-90,04:00-05:15` or `04:00-+75`. Can be repeated.
+    /// Fractional seconds must use `.` rather than `,` here.
+    pub keep: Vec<String>,
+    #[clap(
+        short,
+        long,
+        value_delimiter = ',',
+        allow_hyphen_values = true,
+        conflicts_with = "keep"
+    )]
+    /// Ranges of the video to remove, everything else will be kept and joined together into a single output file.
+    /// Provided as a comma separated list of `START-END` pairs, e.g. `01:00-01:45`. Can be repeated.
+    /// Fractional seconds must use `.` rather than `,` here.
+    pub cut: Vec<String>,
+    #[clap(short, long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    /// The number of files to trim at once when trimming more than one file.
+    pub jobs: u32,
+    #[clap(short, long)]
+    /// Don't show the progress bar while trimming.
+    pub quiet: bool,
+    #[clap(flatten)]
+    pub black: BlackArgs,
+    #[clap(flatten)]
+    pub silence: SilenceArgs,
+    #[clap(flatten)]
+    pub encode: EncodeArgs,
     #[clap(short, long)]
     /// The output location for the trimmed video file.
     /// If not provided, the trimmed video will be saved in the same directory as the original video. Can be a specific file or a directory in which case a filename will be generated.
+    /// Must be a directory when trimming more than one file.
+    pub output: Option<path::PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Split a video into several parts, either a number of equal parts or fixed length chunks.
+    Split(SplitArgs),
+    /// List the chapters of a video.
+    Chapters(ChaptersArgs),
+    /// List the scene changes of a video, or split it into one part per scene.
+    Scenes(ScenesArgs),
+    /// Show the format, streams and chapters of a video.
+    Probe(ProbeArgs),
+    /// Run every trim listed in a CSV, JSON or YAML manifest.
+    Run(RunArgs),
+}
+
+#[derive(clap::Args, Debug)]
+pub struct RunArgs {
+    /// Path to the manifest, a `.csv` file with the columns `file`, `start`, `end` and `output`,
+    /// or a `.json` or `.yaml` file containing a list of objects with those fields.
+    /// Only `file` is required, relative paths are resolved from the manifest's directory.
+    pub manifest: path::PathBuf,
+    #[clap(long)]
+    /// Check every job and show what would be trimmed without trimming anything.
+    pub dry_run: bool,
+    #[clap(short, long)]
+    /// The directory to save the trimmed videos in.
+    /// If not provided, they will be saved next to the manifest.
+    pub output: Option<path::PathBuf>,
+    #[clap(short, long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    /// The number of jobs to run at once.
+    pub jobs: u32,
+    #[clap(short, long)]
+    /// Don't show the progress bar while trimming.
+    pub quiet: bool,
+    #[clap(flatten)]
+    pub encode: EncodeArgs,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ProbeArgs {
+    /// Path to the video file to probe.
+    pub target_file: path::PathBuf,
+    #[clap(long)]
+    /// Print the information as JSON instead.
+    pub json: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ChaptersArgs {
+    /// Path to the video file to list the chapters of.
+    pub target_file: path::PathBuf,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ScenesArgs {
+    /// Path to the video file to find the scene changes of.
+    pub target_file: path::PathBuf,
+    #[clap(short, long, default_value_t = 0.4)]
+    /// How different a frame must be from the previous frame to count as a scene change, from 0 to 1.
+    pub threshold: f64,
+    #[clap(short, long)]
+    /// Split the video into one part per scene instead of listing the scene changes.
+    pub split: bool,
+    #[clap(short, long, requires = "split")]
+    /// The directory to save the parts in.
+    /// If not provided, the parts will be saved in the current directory.
+    pub output: Option<path::PathBuf>,
+    #[clap(short, long)]
+    /// Don't show the progress bar while trimming.
+    pub quiet: bool,
+    #[clap(flatten)]
+    pub encode: EncodeArgs,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct SplitArgs {
+    /// Path to the video file to be split.
+    pub target_file: path::PathBuf,
+    #[clap(
+        short,
+        long,
+        value_parser = clap::value_parser!(u32).range(1..),
+        required_unless_present_any = ["every", "by_chapter"],
+        conflicts_with_all = ["every", "by_chapter"]
+    )]
+    /// The number of equal length parts to split the video into.
+    pub parts: Option<u32>,
+    #[clap(short, long)]
+    /// The length of each part, the final part will contain whatever is left over.
+    /// Can be provided as a timestamp in the format `HH:MM:SS`, `MM:SS`, a percentage `DD.D%` or as a number of seconds.
+    /// Seconds can include milliseconds, e.g. `01:23.456`, `12.5` or `00:01:23,456`, or use units such as `1h2m3s`, `90s` or `1500ms`.
+    /// A frame number can be provided as `f1234` or `1234f`, or a SMPTE timecode as `HH:MM:SS:FF` or `HH:MM:SS;FF` for drop-frame.
+    pub every: Option<String>,
+    #[clap(short, long, conflicts_with_all = ["every", "snap"])]
+    /// Split the video into one part per chapter, named after the chapter titles.
+    pub by_chapter: bool,
+    #[clap(short, long)]
+    /// The directory to save the parts in.
+    /// If not provided, the parts will be saved in the current directory.
     pub output: Option<path::PathBuf>,
+    #[clap(short, long)]
+    /// Don't show the progress bar while trimming.
+    pub quiet: bool,
+    #[clap(flatten)]
+    pub encode: EncodeArgs,
+}
+
+#[derive(clap::Args, Debug, Default)]
+pub struct BlackArgs {
+    #[clap(long)]
+    /// Move the start and end of the trimmed video past any black frames, using ffmpeg's blackdetect filter.
+    pub trim_black: bool,
+    #[clap(long, default_value_t = 0.1, requires = "trim_black")]
+    /// The brightness from 0 to 1 below which a pixel counts as black.
+    pub black_threshold: f64,
+    #[clap(long, default_value_t = 0.5, requires = "trim_black")]
+    /// The minimum length in seconds of a run of black frames for it to be removed.
+    pub black_duration: f64,
+}
+
+#[derive(clap::Args, Debug, Default)]
+#[clap(group(ArgGroup::new("silence").args(["trim_silence", "jump_cut"]).multiple(true)))]
+pub struct SilenceArgs {
+    #[clap(long)]
+    /// Move the start and end of the trimmed video past any silence, using ffmpeg's silencedetect filter.
+    pub trim_silence: bool,
+    #[clap(long)]
+    /// Remove every span of silence from the trimmed video and join the rest together, using ffmpeg's silencedetect filter.
+    pub jump_cut: bool,
+    #[clap(long, default_value_t = -30.0, allow_hyphen_values = true, requires = "silence")]
+    /// The volume in dB below which audio counts as silence.
+    pub silence_threshold: f64,
+    #[clap(long, default_value_t = 0.5, requires = "silence")]
+    /// The minimum length in seconds of a quiet span for it to count as silence.
+    pub silence_duration: f64,
+    #[clap(long, default_value_t = 0.25, requires = "jump_cut")]
+    /// The number of seconds of silence to keep either side of the sound when using `--jump-cut`.
+    pub padding: f64,
+}
+
+#[derive(clap::Args, Debug, Default)]
+#[clap(group(ArgGroup::new("reencode").args(["accurate", "smart"])))]
+pub struct EncodeArgs {
+    #[clap(long)]
+    /// Re-encode the video so that the start and end are frame accurate.
+    /// Slower than the default, which copies the streams and snaps the cuts to the nearest keyframe.
+    pub accurate: bool,
+    #[clap(long)]
+    /// Frame accurate like `--accurate`, but only re-encodes the video around the start and end and copies the rest.
+    /// Much faster for long videos. The re-encoded parts match the profile, pixel format and timebase of the input video.
+    pub smart: bool,
+    #[clap(long, requires = "reencode")]
+    /// The video encoder to re-encode with when using `--accurate` or `--smart`.
+    /// Defaults to `libx264` with `--accurate`, and to an encoder for the input video's codec with `--smart`.
+    pub codec: Option<String>,
+    #[clap(long, default_value_t = 18, requires = "reencode")]
+    /// The constant rate factor to re-encode with when using `--accurate` or `--smart`, lower is better quality.
+    pub crf: u8,
+    #[clap(long, default_value = "medium", requires = "reencode")]
+    /// The encoder preset to re-encode with when using `--accurate` or `--smart`, slower presets give smaller files.
+    pub preset: String,
+    #[clap(long, value_enum, conflicts_with_all = ["accurate", "smart"])]
+    /// Which keyframe the start and end should be moved onto when copying the streams.
+    /// Without this ffmpeg decides where the cut lands.
+    pub snap: Option<Snap>,
+}
+
+impl EncodeArgs {
+    pub fn get_trim_mode(&self) -> TrimMode {
+        let options = EncodeOptions {
+            codec: self.codec.clone(),
+            crf: self.crf,
+            preset: self.preset.clone(),
+        };
+
+        if self.accurate {
+            TrimMode::Accurate(options)
+        } else if self.smart {
+            TrimMode::SmartCut(options)
+        } else {
+            TrimMode::Copy
+        }
+    }
+}
+
+impl SplitArgs {
+    pub fn get_every_timestamp(&self) -> Result<Option<Timestamp>, Error> {
+        self.every
+            .as_deref()
+            .map(Timestamp::parse_timestamp)
+            .transpose()
+    }
 }
 
 impl Args {
     pub fn get_start_timestamp(&self) -> Result<Timestamp, Error> {
+        if let Some(chapter) = self.chapter.as_ref().or(self.from_chapter.as_ref()) {
+            return Ok(Timestamp::ChapterStart(ChapterRef::parse_chapter_ref(
+                chapter,
+            )));
+        }
         get_timestamp(self.start_timestamp.clone(), true)
     }
 
     pub fn get_end_timestamp(&self) -> Result<Timestamp, Error> {
+        if let Some(chapter) = self.chapter.as_ref().or(self.to_chapter.as_ref()) {
+            return Ok(Timestamp::ChapterEnd(ChapterRef::parse_chapter_ref(
+                chapter,
+            )));
+        }
+        if let Some(duration) = &self.duration {
+            return Timestamp::parse_duration(duration);
+        }
         get_timestamp(self.end_timestamp.clone(), false)
     }
+
+    pub fn get_keep_ranges(&self) -> Result<Vec<TimeRange>, Error> {
+        get_ranges(&self.keep)
+    }
+
+    pub fn get_cut_ranges(&self) -> Result<Vec<TimeRange>, Error> {
+        get_ranges(&self.cut)
+    }
+
+    /// Expands any glob patterns in the target files, keeping the files in the order they were given.
+    pub fn get_target_files(&self) -> Result<Vec<TargetFile>, Error> {
+        let mut target_files = Vec::new();
+
+        for target_file in &self.target_files {
+            let pattern = target_file.to_string_lossy();
+
+            // Existing files are never treated as patterns, as names such as `My Video [HD].mp4` are common.
+            // Plain paths are kept as they are, so that a missing file is reported like any other failure
+            if target_file.exists() || !pattern.contains(['*', '?', '[']) {
+                target_files.push(TargetFile::File(target_file.clone()));
+                continue;
+            }
+
+            let matches = glob::glob(&pattern)
+                .map_err(|_| Error::InvalidGlob(pattern.to_string()))?
+                .filter_map(Result::ok)
+                .filter(|path| path.is_file())
+                .map(TargetFile::File)
+                .collect::<Vec<_>>();
+
+            if matches.is_empty() {
+                target_files.push(TargetFile::Unmatched(pattern.to_string()));
+            }
+
+            target_files.extend(matches);
+        }
+
+        let mut seen = HashSet::new();
+        target_files.retain(|target_file| seen.insert(target_file.clone()));
+
+        Ok(target_files)
+    }
+
+    pub fn get_trim_job(&self, target_file: &path::Path) -> Result<TrimJob, Error> {
+        let mut job = TrimJob {
+            start: self.get_start_timestamp()?,
+            end: self.get_end_timestamp()?,
+            keep: self.get_keep_ranges()?,
+            cut: self.get_cut_ranges()?,
+            output: self.output.clone(),
+            mode: self.encode.get_trim_mode(),
+            snap: self.encode.snap,
+            silence: SilenceOptions {
+                threshold_db: self.silence.silence_threshold,
+                min_duration: self.silence.silence_duration,
+            },
+            trim_silence: self.silence.trim_silence,
+            ..TrimJob::new(target_file)
+        };
+
+        if self.black.trim_black {
+            job = job.trim_black(BlackOptions {
+                pixel_threshold: self.black.black_threshold,
+                min_duration: self.black.black_duration,
+            });
+        }
+
+        if self.silence.jump_cut {
+            job = job.jump_cut(self.silence.padding);
+        }
+
+        Ok(job)
+    }
+}
+
+fn get_ranges(arg_ranges: &[String]) -> Result<Vec<TimeRange>, Error> {
+    arg_ranges
+        .iter()
+        .map(|range| TimeRange::parse_range(range))
+        .collect()
 }
 
 fn get_timestamp(arg_timestamp: Option<String>, start: bool) -> Result<Timestamp, Error> {
     if let Some(timestamp) = arg_timestamp {
-        Timestamp::parse_timestamp(&timestamp)
+        if start {
+            Timestamp::parse_start(&timestamp)
+        } else {
+            Timestamp::parse_timestamp(&timestamp)
+        }
     } else {
         if start {
             Ok(Timestamp::Start)
@@ -45,3 +403,85 @@ fn get_timestamp(arg_timestamp: Option<String>, start: bool) -> Result<Timestamp
         }
     }
 }
+
+/// A file to be trimmed, or a glob pattern which didn't match any files.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TargetFile {
+    File(path::PathBuf),
+    Unmatched(String),
+}
+
+impl TargetFile {
+    /// The file, or the pattern for one which matched nothing, to report the result against.
+    pub fn path(&self) -> path::PathBuf {
+        match self {
+            TargetFile::File(path) => path.clone(),
+            TargetFile::Unmatched(pattern) => path::PathBuf::from(pattern),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn args_with_target_files(dir: &path::Path, target_files: &[&str]) -> Args {
+        Args {
+            target_files: target_files.iter().map(|file| dir.join(file)).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn get_target_files_expands_globs_in_order() {
+        let dir = std::env::temp_dir().join(format!("ttrim_args_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for file in ["b.mp4", "a.mp4", "c.txt"] {
+            fs::write(dir.join(file), "").unwrap();
+        }
+
+        let args = args_with_target_files(&dir, &["z.mp4", "*.mp4", "a.mp4"]);
+        let result = args.get_target_files();
+
+        fs::remove_dir_all(&dir).unwrap();
+        let expected = ["z.mp4", "a.mp4", "b.mp4"]
+            .map(|file| TargetFile::File(dir.join(file)))
+            .to_vec();
+        assert_eq!(result, Ok(expected));
+    }
+
+    #[test]
+    fn get_target_files_keeps_glob_which_matches_nothing() {
+        let dir = std::env::temp_dir();
+        let args = args_with_target_files(&dir, &["ttrim_no_such_file_*.mkv"]);
+        let result = args.get_target_files();
+        let expected = vec![TargetFile::Unmatched(
+            dir.join("ttrim_no_such_file_*.mkv")
+                .to_string_lossy()
+                .to_string(),
+        )];
+        assert_eq!(result, Ok(expected));
+    }
+
+    #[test]
+    fn get_target_files_does_not_expand_existing_files() {
+        let dir = std::env::temp_dir().join(format!("ttrim_args_brackets_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("My Video [HD].mp4"), "").unwrap();
+
+        let args = args_with_target_files(&dir, &["My Video [HD].mp4"]);
+        let result = args.get_target_files();
+
+        fs::remove_dir_all(&dir).unwrap();
+        let expected = vec![TargetFile::File(dir.join("My Video [HD].mp4"))];
+        assert_eq!(result, Ok(expected));
+    }
+
+    #[test]
+    fn snap_conflict_names_the_re_encoding_flag_given() {
+        let result = Args::try_parse_from(["ttrim", "video.mp4", "--snap", "nearest", "--smart"]);
+        let message = result.unwrap_err().to_string();
+        assert!(message.contains("'--smart'"), "{}", message);
+    }
+}
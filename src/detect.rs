@@ -0,0 +1,363 @@
+use crate::error::Error;
+use crate::video_utils;
+
+use regex::Regex;
+use std::path::Path;
+use std::process::Command;
+use std::str;
+
+/// Runs ffmpeg's `silencedetect` filter over the part of the video between `start` and `end`, and
+/// returns the start and end of each span of silence. Audio quieter than `threshold_db` for at least
+/// `min_duration` seconds counts as silence.
+pub fn detect_silence(
+    path: &Path,
+    threshold_db: f64,
+    min_duration: f64,
+    (start, end): (f64, f64),
+) -> Result<Vec<(f64, f64)>, Error> {
+    let log = run_detection_filter(
+        path,
+        Some((start, end)),
+        "-af",
+        &format!("silencedetect=noise={}dB:d={}", threshold_db, min_duration),
+    )?;
+
+    Ok(offset_spans(parse_silence_log(&log, end - start), start))
+}
+
+/// Runs ffmpeg's `blackdetect` filter over the part of the video between `start` and `end`, and
+/// returns the start and end of each span of black frames. Pixels darker than `pixel_threshold`
+/// (0 to 1) count as black, and frames must be black for at least `min_duration` seconds.
+pub fn detect_black(
+    path: &Path,
+    pixel_threshold: f64,
+    min_duration: f64,
+    (start, end): (f64, f64),
+) -> Result<Vec<(f64, f64)>, Error> {
+    let log = run_detection_filter(
+        path,
+        Some((start, end)),
+        "-vf",
+        &format!("blackdetect=d={}:pix_th={}", min_duration, pixel_threshold),
+    )?;
+
+    Ok(offset_spans(parse_black_log(&log, end - start), start))
+}
+
+/// Runs ffmpeg's `select` filter over the video and returns the time of each frame whose scene
+/// change score (0 to 1) is greater than `threshold`.
+pub fn detect_scenes(path: &Path, threshold: f64) -> Result<Vec<f64>, Error> {
+    let log = run_detection_filter(
+        path,
+        None,
+        "-vf",
+        &format!("select='gt(scene,{})',showinfo", threshold),
+    )?;
+
+    Ok(parse_scene_log(&log))
+}
+
+/// Moves the start of each segment forward past any span (of silence or black frames) it begins in,
+/// and the end of each segment back before any span it finishes in. Segments which are entirely
+/// within a span are dropped.
+pub fn trim_spans(segments: &[(f64, f64)], spans: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    segments
+        .iter()
+        .map(|(start, end)| {
+            let start = spans
+                .iter()
+                .find(|(span_start, span_end)| span_start <= start && start < span_end)
+                .map_or(*start, |(_, span_end)| *span_end);
+            let end = spans
+                .iter()
+                .find(|(span_start, span_end)| span_start < end && end <= span_end)
+                .map_or(*end, |(span_start, _)| *span_start);
+            (start, end)
+        })
+        .filter(|(start, end)| start < end)
+        .collect()
+}
+
+/// Removes every span of silence from the segments, splitting them into the parts with sound.
+/// `padding` seconds of each silence are kept next to the sound so that speech isn't clipped.
+/// Silence running into the start or end of a segment isn't padded on that side, as there is
+/// no sound there to protect.
+pub fn jump_cut(segments: &[(f64, f64)], silences: &[(f64, f64)], padding: f64) -> Vec<(f64, f64)> {
+    let mut kept = Vec::new();
+
+    for (start, end) in segments {
+        let mut position = *start;
+
+        for (silence_start, silence_end) in silences {
+            if silence_end <= start || silence_start >= end {
+                continue;
+            }
+
+            let cut_start = if silence_start <= start {
+                *start
+            } else {
+                silence_start + padding
+            };
+            let cut_end = if silence_end >= end {
+                *end
+            } else {
+                silence_end - padding
+            };
+
+            if cut_end <= position || cut_start >= cut_end {
+                continue;
+            }
+            if cut_start > position {
+                kept.push((position, cut_start));
+            }
+            position = cut_end;
+        }
+
+        if position < *end {
+            kept.push((position, *end));
+        }
+    }
+
+    kept
+}
+
+/// Runs ffmpeg with the given detection filter, discarding the output, and returns its log
+/// which the filters write their results to. If a range is given only that part of the video is
+/// read, and the times in the log are relative to its start.
+fn run_detection_filter(
+    path: &Path,
+    range: Option<(f64, f64)>,
+    filter_arg: &str,
+    filter: &str,
+) -> Result<String, Error> {
+    if !video_utils::check_program_installed("ffmpeg".to_owned()) {
+        Err(Error::FfpmegNotInstalled)?
+    }
+
+    let mut command = Command::new("ffmpeg");
+    command.arg("-hide_banner").arg("-nostats");
+
+    if let Some((start, end)) = range {
+        command
+            .arg("-ss")
+            .arg(format!("{}", start))
+            .arg("-t")
+            .arg(format!("{}", end - start));
+    }
+
+    // Only the stream being filtered is decoded, as decoding video just to listen to the
+    // audio makes detection many times slower
+    let skip_other_streams = match filter_arg {
+        "-af" => "-vn",
+        _ => "-an",
+    };
+
+    let output = command
+        .arg("-i")
+        .arg(path)
+        .arg(skip_other_streams)
+        .arg("-sn")
+        .arg(filter_arg)
+        .arg(filter)
+        .arg("-f")
+        .arg("null")
+        .arg("-")
+        .output();
+
+    let output = match output {
+        Ok(output) => match output.status.success() {
+            true => output,
+            false => return Err(Error::FfpmegError)?,
+        },
+        Err(_) => return Err(Error::FfpmegError)?,
+    };
+
+    str::from_utf8(&output.stderr)
+        .map(str::to_owned)
+        .map_err(|_| {
+            Error::InvalidData("Failed to read ffmpeg output to a UTF-8 string".to_owned())
+        })
+}
+
+/// Moves spans detected in part of a video, whose times start from 0, back to where they are in the video.
+fn offset_spans(spans: Vec<(f64, f64)>, offset: f64) -> Vec<(f64, f64)> {
+    spans
+        .into_iter()
+        .map(|(start, end)| (start + offset, end + offset))
+        .collect()
+}
+
+/// Parses the `silence_start` and `silence_end` lines logged by `silencedetect`.
+/// Silence which runs until the end of the video has no `silence_end`, so it ends at the video length.
+fn parse_silence_log(log: &str, video_length: f64) -> Vec<(f64, f64)> {
+    let regex = Regex::new(r"silence_(start|end): (-?[\d.]+)").unwrap();
+
+    let mut silences = Vec::new();
+    let mut silence_start = None;
+
+    for cap in regex.captures_iter(log) {
+        let Ok(seconds) = cap[2].parse::<f64>() else {
+            continue;
+        };
+
+        match &cap[1] {
+            "start" => silence_start = Some(seconds.max(0.0)),
+            _ => {
+                if let Some(start) = silence_start.take() {
+                    silences.push((start, seconds));
+                }
+            }
+        }
+    }
+
+    if let Some(start) = silence_start {
+        silences.push((start, video_length));
+    }
+
+    silences
+}
+
+/// Parses the `black_start` and `black_end` pairs logged by `blackdetect`, clamping them to the video.
+fn parse_black_log(log: &str, video_length: f64) -> Vec<(f64, f64)> {
+    let regex = Regex::new(r"black_start:\s*(-?[\d.]+)\s+black_end:\s*(-?[\d.]+)").unwrap();
+
+    regex
+        .captures_iter(log)
+        .filter_map(|cap| {
+            let start = cap[1].parse::<f64>().ok()?;
+            let end = cap[2].parse::<f64>().ok()?;
+            Some((start.max(0.0), end.min(video_length)))
+        })
+        .filter(|(start, end)| start < end)
+        .collect()
+}
+
+/// Parses the `pts_time` of each frame logged by `showinfo`, which only sees the frames that
+/// passed the scene change `select`.
+fn parse_scene_log(log: &str) -> Vec<f64> {
+    let regex = Regex::new(r"Parsed_showinfo.*pts_time:\s*([\d.]+)").unwrap();
+
+    regex
+        .captures_iter(log)
+        .filter_map(|cap| cap[1].parse::<f64>().ok())
+        .filter(|seconds| *seconds > 0.0)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_silence_log_reads_spans() {
+        let log = "[silencedetect @ 0x1] silence_start: 0\n\
+                   [silencedetect @ 0x1] silence_end: 5.25 | silence_duration: 5.25\n\
+                   [silencedetect @ 0x1] silence_start: 60.5\n\
+                   [silencedetect @ 0x1] silence_end: 62 | silence_duration: 1.5\n";
+        let result = parse_silence_log(log, 100.0);
+        let expected = vec![(0.0, 5.25), (60.5, 62.0)];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn offset_spans_moves_spans_to_scanned_range() {
+        let log = "[silencedetect @ 0x1] silence_start: 0
+                   [silencedetect @ 0x1] silence_end: 2 | silence_duration: 2
+                   [silencedetect @ 0x1] silence_start: 25
+";
+        let result = offset_spans(parse_silence_log(log, 30.0), 60.0);
+        let expected = vec![(60.0, 62.0), (85.0, 90.0)];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn parse_silence_log_ends_trailing_silence_at_video_length() {
+        let log = "[silencedetect @ 0x1] silence_start: 95.5\n";
+        let result = parse_silence_log(log, 100.0);
+        let expected = vec![(95.5, 100.0)];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn parse_silence_log_clamps_negative_start() {
+        let log = "[silencedetect @ 0x1] silence_start: -0.01\n\
+                   [silencedetect @ 0x1] silence_end: 3 | silence_duration: 3.01\n";
+        let result = parse_silence_log(log, 100.0);
+        let expected = vec![(0.0, 3.0)];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn parse_black_log_reads_spans() {
+        let log = "[blackdetect @ 0x1] black_start:0 black_end:2.5 black_duration:2.5\n\
+                   [blackdetect @ 0x1] black_start:97.96 black_end:100.04 black_duration:2.08\n";
+        let result = parse_black_log(log, 100.0);
+        let expected = vec![(0.0, 2.5), (97.96, 100.0)];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn parse_scene_log_reads_frame_times() {
+        let log = "[Parsed_showinfo_1 @ 0x1] config in time_base: 1/30000, frame_rate: 30000/1001\n\
+                   [Parsed_showinfo_1 @ 0x1] n:   0 pts:  150150 pts_time:5.005   duration:   1001\n\
+                   [Parsed_showinfo_1 @ 0x1] n:   1 pts:  372372 pts_time:12.4124 duration:   1001\n";
+        let result = parse_scene_log(log);
+        let expected = vec![5.005, 12.4124];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn trim_spans_moves_start_and_end() {
+        let silences = vec![(0.0, 12.0), (50.0, 55.0), (90.0, 100.0)];
+        let result = trim_spans(&[(0.0, 100.0)], &silences);
+        let expected = vec![(12.0, 90.0)];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn trim_spans_leaves_segments_starting_outside_spans() {
+        let silences = vec![(0.0, 12.0), (90.0, 100.0)];
+        let result = trim_spans(&[(20.0, 80.0)], &silences);
+        let expected = vec![(20.0, 80.0)];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn trim_spans_drops_segments_within_a_span() {
+        let silences = vec![(0.0, 12.0)];
+        let result = trim_spans(&[(2.0, 10.0)], &silences);
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn jump_cut_removes_internal_silence_with_padding() {
+        let silences = vec![(0.0, 5.0), (20.0, 30.0), (95.0, 100.0)];
+        let result = jump_cut(&[(0.0, 100.0)], &silences, 0.5);
+        let expected = vec![(4.5, 20.5), (29.5, 95.5)];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn jump_cut_ignores_silence_shorter_than_padding() {
+        let silences = vec![(20.0, 20.8)];
+        let result = jump_cut(&[(0.0, 100.0)], &silences, 0.5);
+        let expected = vec![(0.0, 100.0)];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn jump_cut_only_cuts_within_segments() {
+        let silences = vec![(20.0, 30.0), (60.0, 70.0)];
+        let result = jump_cut(&[(25.0, 50.0)], &silences, 0.0);
+        let expected = vec![(30.0, 50.0)];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn jump_cut_only_pads_silence_next_to_sound() {
+        let silences = vec![(20.0, 30.0), (45.0, 60.0)];
+        let result = jump_cut(&[(25.0, 50.0)], &silences, 0.5);
+        let expected = vec![(29.5, 45.5)];
+        assert_eq!(result, expected);
+    }
+}
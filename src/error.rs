@@ -1,27 +1,70 @@
 use thiserror::Error;
 
 #[derive(Debug, Error, PartialEq)]
+#[allow(clippy::enum_variant_names)]
 pub enum Error {
     #[error("{0}")]
     PercentageOutOfRange(String),
     #[error("{0}")]
     InvalidTime(String),
-    #[error("Provided timestamp did not match the format (HH:MM:SS) or (NN) for number of seconds or (NN%) for percentage")]
+    #[error("Provided timestamp did not match the format (HH:MM:SS.mmm) or (NN.mmm) for number of seconds or (NN.N%) for percentage")]
     NoTimestampMatch,
+    #[error("Could not determine the frame rate of the video, which is needed for frame number timestamps")]
+    UnknownFrameRate,
+    #[error(
+        "Could not find chapter {0}, use `ttrim chapters <file>` to list the chapters of a video"
+    )]
+    ChapterNotFound(String),
+    #[error("Provided video does not have any chapters")]
+    NoChapters,
     #[error("Provided file has an invalid or unknown video file extension")]
     InvalidExtension,
     #[error("Provided video file does not exist")]
     InputFileDoesntExist,
+    #[error("Provided file does not contain any video or audio streams")]
+    NoMediaStreams,
     #[error("Provided end timestamp is before start timestamp")]
     EndTimestampBeforeStartTimestamp,
+    #[error("Invalid range: {0}, expected the format START-END")]
+    InvalidRange(String),
+    #[error("Provided ranges overlap")]
+    OverlappingRanges,
+    #[error("Provided ranges would remove the entire video")]
+    NothingToKeep,
+    #[error("Only silence was found, try lowering the silence threshold")]
+    OnlySilence,
+    #[error("Only black frames were found, try lowering the black threshold")]
+    OnlyBlackFrames,
+    #[error(
+        "There is nothing left to keep after snapping to keyframes, try a different snap option"
+    )]
+    NothingToKeepAfterSnapping,
     #[error("Output file already exists")]
     OutputFileExists,
+    #[error("Provided output must be an existing directory")]
+    OutputNotDirectory,
     #[error("An error occurred while running ffmpeg")]
     FfpmegError,
     #[error("Ffmpeg is not installed, please install it and try again. You can find details here: https://ffmpeg.org/download.html")]
     FfpmegNotInstalled,
     #[error("Ffprobe is not installed, please install it and try again. You can find details here: https://ffmpeg.org/download.html")]
     FfprobeNotInstalled,
+    #[error("Invalid glob pattern: {0}")]
+    InvalidGlob(String),
+    #[error("No files matched {0}")]
+    NoFilesMatched(String),
+    #[error("Output must be a directory when trimming more than one file")]
+    OutputNotDirectoryForBatch,
+    #[error("{0} of {1} files failed to trim")]
+    BatchFailed(usize, usize),
+    #[error("Invalid manifest: {0}")]
+    InvalidManifest(String),
+    #[error("{0} of {1} jobs in the manifest are invalid, nothing was trimmed")]
+    InvalidManifestJobs(usize, usize),
+    #[error("Output {0} is used by more than one job")]
+    DuplicateOutput(String),
+    #[error("Smart cutting {0} video needs an encoder for it, provide one with --codec or use --accurate")]
+    UnsupportedSmartCutCodec(String),
     #[error("Invalid path: {0}")]
     InvalidPath(String),
     #[error("Invalid data: {0}")]
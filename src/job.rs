@@ -0,0 +1,668 @@
+use crate::detect;
+use crate::error::Error;
+use crate::media_info::{self, MediaInfo};
+use crate::range::{self, TimeRange};
+use crate::timestamp::{Snap, Timestamp, TimestampContext};
+use crate::video_utils::{self, PathType, Progress, ProgressTracker, TrimInput, TrimMode};
+
+use std::collections::HashSet;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Options for finding black frames with ffmpeg's `blackdetect` filter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlackOptions {
+    /// The brightness from 0 to 1 below which a pixel counts as black.
+    pub pixel_threshold: f64,
+    /// The minimum length in seconds of a run of black frames.
+    pub min_duration: f64,
+}
+
+impl Default for BlackOptions {
+    fn default() -> Self {
+        BlackOptions {
+            pixel_threshold: 0.1,
+            min_duration: 0.5,
+        }
+    }
+}
+
+/// Options for finding silence with ffmpeg's `silencedetect` filter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SilenceOptions {
+    /// The volume in dB below which audio counts as silence.
+    pub threshold_db: f64,
+    /// The minimum length in seconds of a quiet span for it to count as silence.
+    pub min_duration: f64,
+}
+
+impl Default for SilenceOptions {
+    fn default() -> Self {
+        SilenceOptions {
+            threshold_db: -30.0,
+            min_duration: 0.5,
+        }
+    }
+}
+
+/// Describes a single trim of one input video, built up with the builder methods and run by a [`Trimmer`].
+///
+/// The parts of the video to keep are taken from the keep ranges if there are any, otherwise
+/// everything outside the cut ranges, otherwise from the start to the end timestamps.
+#[derive(Debug, Clone)]
+pub struct TrimJob {
+    pub input: PathBuf,
+    pub start: Timestamp,
+    pub end: Timestamp,
+    pub keep: Vec<TimeRange>,
+    pub cut: Vec<TimeRange>,
+    /// A file or an existing directory to write the output to. A filename is generated if not provided.
+    pub output: Option<PathBuf>,
+    pub mode: TrimMode,
+    pub snap: Option<Snap>,
+    pub trim_black: Option<BlackOptions>,
+    pub silence: SilenceOptions,
+    pub trim_silence: bool,
+    /// The padding kept either side of the sound when jump cutting, if jump cutting.
+    pub jump_cut: Option<f64>,
+}
+
+impl TrimJob {
+    pub fn new(input: impl Into<PathBuf>) -> TrimJob {
+        TrimJob {
+            input: input.into(),
+            start: Timestamp::Start,
+            end: Timestamp::End,
+            keep: Vec::new(),
+            cut: Vec::new(),
+            output: None,
+            mode: TrimMode::Copy,
+            snap: None,
+            trim_black: None,
+            silence: SilenceOptions::default(),
+            trim_silence: false,
+            jump_cut: None,
+        }
+    }
+
+    pub fn start(mut self, start: Timestamp) -> TrimJob {
+        self.start = start;
+        self
+    }
+
+    pub fn end(mut self, end: Timestamp) -> TrimJob {
+        self.end = end;
+        self
+    }
+
+    /// Adds a range to keep, all of the kept ranges are joined together into the output.
+    pub fn keep(mut self, range: TimeRange) -> TrimJob {
+        self.keep.push(range);
+        self
+    }
+
+    /// Adds a range to remove, everything else is joined together into the output.
+    pub fn cut(mut self, range: TimeRange) -> TrimJob {
+        self.cut.push(range);
+        self
+    }
+
+    pub fn output(mut self, output: impl Into<PathBuf>) -> TrimJob {
+        self.output = Some(output.into());
+        self
+    }
+
+    pub fn mode(mut self, mode: TrimMode) -> TrimJob {
+        self.mode = mode;
+        self
+    }
+
+    pub fn snap(mut self, snap: Snap) -> TrimJob {
+        self.snap = Some(snap);
+        self
+    }
+
+    /// Moves the start and end past any black frames.
+    pub fn trim_black(mut self, options: BlackOptions) -> TrimJob {
+        self.trim_black = Some(options);
+        self
+    }
+
+    /// Sets how silence is found for [`TrimJob::trim_silence`] and [`TrimJob::jump_cut`].
+    pub fn silence(mut self, options: SilenceOptions) -> TrimJob {
+        self.silence = options;
+        self
+    }
+
+    /// Moves the start and end past any silence.
+    pub fn trim_silence(mut self) -> TrimJob {
+        self.trim_silence = true;
+        self
+    }
+
+    /// Removes every span of silence, keeping `padding` seconds of it either side of the sound.
+    pub fn jump_cut(mut self, padding: f64) -> TrimJob {
+        self.jump_cut = Some(padding);
+        self
+    }
+
+    /// Resolves the parts of the video to keep, before any silence, black frames or snapping is applied.
+    pub fn resolve_segments(&self, context: &TimestampContext) -> Result<Vec<(f64, f64)>, Error> {
+        if !self.keep.is_empty() {
+            return range::resolve_ranges(&self.keep, context);
+        }
+
+        if !self.cut.is_empty() {
+            let cut_segments = range::resolve_ranges(&self.cut, context)?;
+            let kept_segments = range::complement_segments(&cut_segments, context.video_length);
+            if kept_segments.is_empty() {
+                Err(Error::NothingToKeep)?
+            }
+            return Ok(kept_segments);
+        }
+
+        let start_seconds = self.start.to_seconds(context)?;
+        let end_seconds = self.end.to_seconds(&context.relative_to(start_seconds))?;
+
+        if end_seconds < start_seconds {
+            Err(Error::EndTimestampBeforeStartTimestamp)?
+        }
+
+        Ok(vec![(start_seconds, end_seconds.min(context.video_length))])
+    }
+}
+
+/// The outcome of a successful [`TrimJob`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrimResult {
+    pub input: PathBuf,
+    pub output: PathBuf,
+    /// The parts of the input which were kept, in seconds, after silence, black frames and snapping.
+    pub segments: Vec<(f64, f64)>,
+    /// The length of the input video in seconds.
+    pub input_duration: f64,
+}
+
+impl TrimResult {
+    /// The length of the output video in seconds.
+    pub fn duration(&self) -> f64 {
+        self.segments.iter().map(|(start, end)| end - start).sum()
+    }
+}
+
+/// A [`TrimJob`] which has been checked against its probed input, with the parts of the video
+/// to keep and the output path worked out. Nothing has been written yet.
+#[derive(Debug, Clone)]
+pub struct PreparedJob {
+    pub job: TrimJob,
+    pub media_info: MediaInfo,
+    /// The parts of the input to keep, before any silence, black frames or snapping is applied.
+    pub segments: Vec<(f64, f64)>,
+    pub output: PathBuf,
+}
+
+type OnProgress = dyn Fn(JobProgress) + Send + Sync;
+
+/// Runs [`TrimJob`]s.
+#[derive(Clone, Default)]
+pub struct Trimmer {
+    on_progress: Option<Arc<OnProgress>>,
+}
+
+impl fmt::Debug for Trimmer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Trimmer")
+            .field("on_progress", &self.on_progress.is_some())
+            .finish()
+    }
+}
+
+impl Trimmer {
+    pub fn new() -> Trimmer {
+        Trimmer::default()
+    }
+
+    /// Calls `on_progress` as ffmpeg writes each output, with the progress measured against
+    /// the length of the trimmed video. When running jobs in parallel it may be called from
+    /// several threads at once.
+    pub fn on_progress(
+        mut self,
+        on_progress: impl Fn(JobProgress) + Send + Sync + 'static,
+    ) -> Trimmer {
+        self.on_progress = Some(Arc::new(on_progress));
+        self
+    }
+
+    /// Probes the input, works out which parts of it to keep and writes them to the output.
+    pub fn run(&self, job: &TrimJob) -> Result<TrimResult, Error> {
+        self.run_prepared(&self.prepare(job)?)
+    }
+
+    fn run_at(&self, index: usize, job: &TrimJob) -> Result<TrimResult, Error> {
+        self.run_prepared_at(index, &self.prepare(job)?)
+    }
+
+    /// Probes the input and checks the job can be run, without writing anything.
+    /// This is quick, so can be used to validate many jobs before running any of them.
+    pub fn prepare(&self, job: &TrimJob) -> Result<PreparedJob, Error> {
+        let media_info = media_info::probe_input(&job.input)?;
+        let context = TimestampContext::from_media_info(&media_info)?;
+        let segments = job.resolve_segments(&context)?;
+        let output = get_output_path(job.output.as_deref(), &job.input)?;
+
+        Ok(PreparedJob {
+            job: job.clone(),
+            media_info,
+            segments,
+            output,
+        })
+    }
+
+    /// Runs several jobs, with up to `workers` of them running at once. `on_finished` is called
+    /// as each job finishes, one at a time. The results are in the same order as the jobs.
+    pub fn run_all(
+        &self,
+        jobs: &[TrimJob],
+        workers: usize,
+        on_finished: impl Fn(JobFinished) + Sync,
+    ) -> Vec<Result<TrimResult, Error>> {
+        run_in_parallel(
+            jobs,
+            workers,
+            |index, job| self.run_at(index, job),
+            |index, finished, result| {
+                on_finished(JobFinished {
+                    index,
+                    finished,
+                    total: jobs.len(),
+                    input: &jobs[index].input,
+                    result,
+                })
+            },
+        )
+    }
+
+    /// Like [`Trimmer::run_all`], for jobs which have already been prepared.
+    pub fn run_all_prepared(
+        &self,
+        prepared_jobs: &[PreparedJob],
+        workers: usize,
+        on_finished: impl Fn(JobFinished) + Sync,
+    ) -> Vec<Result<TrimResult, Error>> {
+        run_in_parallel(
+            prepared_jobs,
+            workers,
+            |index, prepared| self.run_prepared_at(index, prepared),
+            |index, finished, result| {
+                on_finished(JobFinished {
+                    index,
+                    finished,
+                    total: prepared_jobs.len(),
+                    input: &prepared_jobs[index].job.input,
+                    result,
+                })
+            },
+        )
+    }
+
+    /// Runs a job which has already been prepared.
+    pub fn run_prepared(&self, prepared: &PreparedJob) -> Result<TrimResult, Error> {
+        self.run_prepared_at(0, prepared)
+    }
+
+    /// Runs a prepared job, reporting its progress as the job at `index`.
+    fn run_prepared_at(&self, index: usize, prepared: &PreparedJob) -> Result<TrimResult, Error> {
+        let job = &prepared.job;
+        let duration = prepared.media_info.duration()?;
+        let input = TrimInput::new(&job.input, &prepared.media_info);
+
+        let segments = remove_black(prepared.segments.clone(), job, duration)?;
+        let segments = remove_silence(segments, job, duration)?;
+        let segments = match job.snap {
+            Some(snap) => video_utils::snap_to_keyframes(&segments, snap, &input, duration)?,
+            None => segments,
+        };
+
+        let trimmed_length = segments.iter().map(|(start, end)| end - start).sum();
+        let on_progress = |progress: Progress| {
+            if let Some(on_progress) = &self.on_progress {
+                on_progress(JobProgress {
+                    index,
+                    input: &job.input,
+                    progress,
+                });
+            }
+        };
+        let progress = ProgressTracker::new(trimmed_length, &on_progress);
+
+        video_utils::trim_segments(&segments, &input, &prepared.output, &job.mode, &progress)?;
+
+        Ok(TrimResult {
+            input: job.input.clone(),
+            output: prepared.output.clone(),
+            segments,
+            input_duration: duration,
+        })
+    }
+}
+
+/// Reported to the callback set with [`Trimmer::on_progress`] as ffmpeg writes the output of a job.
+#[derive(Debug)]
+pub struct JobProgress<'a> {
+    /// The position of the job in the list of jobs, or 0 when running a single job.
+    pub index: usize,
+    pub input: &'a Path,
+    pub progress: Progress,
+}
+
+/// Reported by [`Trimmer::run_all`] each time a job finishes.
+#[derive(Debug)]
+pub struct JobFinished<'a> {
+    /// The position of the job in the list of jobs.
+    pub index: usize,
+    /// How many of the jobs have finished, including this one.
+    pub finished: usize,
+    pub total: usize,
+    pub input: &'a Path,
+    pub result: &'a Result<TrimResult, Error>,
+}
+
+/// Runs `run` over every item and its index on up to `workers` threads, each taking the next item once it is free.
+/// `on_finished` is called with the index of the item, the number of items finished so far and
+/// the result, and is never called by two threads at once.
+fn run_in_parallel<T, R>(
+    items: &[T],
+    workers: usize,
+    run: impl Fn(usize, &T) -> R + Sync,
+    on_finished: impl Fn(usize, usize, &R) + Sync,
+) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+{
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new((0..items.len()).map(|_| None).collect::<Vec<_>>());
+    let finished = Mutex::new(0);
+
+    thread::scope(|scope| {
+        for _ in 0..workers.clamp(1, items.len().max(1)) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::SeqCst);
+                let Some(item) = items.get(index) else {
+                    break;
+                };
+
+                let result = run(index, item);
+
+                let mut finished = finished.lock().unwrap();
+                *finished += 1;
+                on_finished(index, *finished, &result);
+                results.lock().unwrap()[index] = Some(result);
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|result| result.expect("every item is run"))
+        .collect()
+}
+
+fn remove_black(
+    segments: Vec<(f64, f64)>,
+    job: &TrimJob,
+    duration: f64,
+) -> Result<Vec<(f64, f64)>, Error> {
+    let Some(options) = job.trim_black else {
+        return Ok(segments);
+    };
+
+    let black_frames = detect::detect_black(
+        &job.input,
+        options.pixel_threshold,
+        options.min_duration,
+        scan_range(&segments, duration),
+    )?;
+    let segments = detect::trim_spans(&segments, &black_frames);
+
+    if segments.is_empty() {
+        Err(Error::OnlyBlackFrames)?
+    }
+
+    Ok(segments)
+}
+
+fn remove_silence(
+    segments: Vec<(f64, f64)>,
+    job: &TrimJob,
+    duration: f64,
+) -> Result<Vec<(f64, f64)>, Error> {
+    if !job.trim_silence && job.jump_cut.is_none() {
+        return Ok(segments);
+    }
+
+    let silences = detect::detect_silence(
+        &job.input,
+        job.silence.threshold_db,
+        job.silence.min_duration,
+        scan_range(&segments, duration),
+    )?;
+
+    let mut segments = segments;
+
+    if job.trim_silence {
+        segments = detect::trim_spans(&segments, &silences);
+    }
+
+    if let Some(padding) = job.jump_cut {
+        segments = detect::jump_cut(&segments, &silences, padding);
+    }
+
+    if segments.is_empty() {
+        Err(Error::OnlySilence)?
+    }
+
+    Ok(segments)
+}
+
+/// The part of the video covering every segment, so that detection filters don't have to read
+/// the parts of the video which are being cut anyway.
+fn scan_range(segments: &[(f64, f64)], duration: f64) -> (f64, f64) {
+    let start = segments.iter().map(|(start, _)| *start).reduce(f64::min);
+    let end = segments.iter().map(|(_, end)| *end).reduce(f64::max);
+    (start.unwrap_or(0.0), end.unwrap_or(duration))
+}
+
+/// Works out where the output should be written, generating a filename if the output
+/// is a directory or wasn't provided.
+pub fn get_output_path(output: Option<&Path>, input: &Path) -> Result<PathBuf, Error> {
+    match output {
+        Some(path) => {
+            let mut path = path.to_path_buf();
+            if path.is_dir() {
+                path.push(video_utils::generate_output_filename(
+                    input,
+                    PathType::FileOnly,
+                ))
+            }
+            if path.exists() {
+                return Err(Error::OutputFileExists);
+            }
+            if !video_utils::check_valid_file_extension(&path) {
+                return Err(Error::InvalidExtension);
+            }
+            Ok(path)
+        }
+        None => Ok(video_utils::generate_output_filename(
+            input,
+            PathType::Relative,
+        )),
+    }
+}
+
+/// Fails every job which writes to the same output as an earlier job, so that the jobs in a batch
+/// never write over each other.
+pub fn reject_duplicate_outputs(
+    prepared_jobs: Vec<Result<PreparedJob, Error>>,
+) -> Vec<Result<PreparedJob, Error>> {
+    let mut outputs = HashSet::new();
+
+    prepared_jobs
+        .into_iter()
+        .map(|prepared| {
+            let prepared = prepared?;
+            if !outputs.insert(prepared.output.clone()) {
+                Err(Error::DuplicateOutput(
+                    prepared.output.display().to_string(),
+                ))?
+            }
+            Ok(prepared)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_job_keeps_whole_video() {
+        let job = TrimJob::new("video.mp4");
+        let result = job.resolve_segments(&TimestampContext::with_length(100.0));
+        assert_eq!(result, Ok(vec![(0.0, 100.0)]));
+    }
+
+    #[test]
+    fn resolve_segments_uses_start_and_relative_end() {
+        let job = TrimJob::new("video.mp4")
+            .start(Timestamp::Seconds(10.0))
+            .end(Timestamp::Relative(Box::new(Timestamp::Seconds(30.0))));
+        let result = job.resolve_segments(&TimestampContext::with_length(100.0));
+        assert_eq!(result, Ok(vec![(10.0, 40.0)]));
+    }
+
+    #[test]
+    fn resolve_segments_clamps_end_to_video_length() {
+        let job = TrimJob::new("video.mp4").end(Timestamp::Seconds(150.0));
+        let result = job.resolve_segments(&TimestampContext::with_length(100.0));
+        assert_eq!(result, Ok(vec![(0.0, 100.0)]));
+    }
+
+    #[test]
+    fn resolve_segments_fails_with_end_before_start() {
+        let job = TrimJob::new("video.mp4")
+            .start(Timestamp::Seconds(50.0))
+            .end(Timestamp::Seconds(20.0));
+        let result = job.resolve_segments(&TimestampContext::with_length(100.0));
+        assert_eq!(result, Err(Error::EndTimestampBeforeStartTimestamp));
+    }
+
+    #[test]
+    fn resolve_segments_compares_end_with_resolved_start() {
+        let job = TrimJob::new("video.mp4")
+            .start(Timestamp::Relative(Box::new(Timestamp::Seconds(10.0))))
+            .end(Timestamp::Seconds(15.0));
+        let result = job.resolve_segments(&TimestampContext::with_length(100.0));
+        assert_eq!(result, Ok(vec![(10.0, 15.0)]));
+    }
+
+    #[test]
+    fn resolve_segments_prefers_keep_ranges() {
+        let job = TrimJob::new("video.mp4")
+            .start(Timestamp::Seconds(50.0))
+            .keep(TimeRange::parse_range("60-70").unwrap())
+            .keep(TimeRange::parse_range("10-20").unwrap());
+        let result = job.resolve_segments(&TimestampContext::with_length(100.0));
+        assert_eq!(result, Ok(vec![(10.0, 20.0), (60.0, 70.0)]));
+    }
+
+    #[test]
+    fn resolve_segments_keeps_everything_outside_cut_ranges() {
+        let job = TrimJob::new("video.mp4").cut(TimeRange::parse_range("10-20").unwrap());
+        let result = job.resolve_segments(&TimestampContext::with_length(100.0));
+        assert_eq!(result, Ok(vec![(0.0, 10.0), (20.0, 100.0)]));
+    }
+
+    #[test]
+    fn run_in_parallel_keeps_results_in_order() {
+        let items = (1..=20).collect::<Vec<u64>>();
+        let finished = Mutex::new(Vec::new());
+
+        let results = run_in_parallel(
+            &items,
+            4,
+            |_, item| {
+                thread::sleep(std::time::Duration::from_millis(20 - item));
+                item * 2
+            },
+            |index, count, result| finished.lock().unwrap().push((index, count, *result)),
+        );
+
+        assert_eq!(results, (1..=20).map(|item| item * 2).collect::<Vec<_>>());
+
+        let finished = finished.into_inner().unwrap();
+        let counts = finished
+            .iter()
+            .map(|(_, count, _)| *count)
+            .collect::<Vec<_>>();
+        assert_eq!(counts, (1..=20).collect::<Vec<_>>());
+        assert!(finished
+            .iter()
+            .all(|(index, _, result)| *result == items[*index] * 2));
+    }
+
+    #[test]
+    fn run_in_parallel_with_no_items() {
+        let results = run_in_parallel(&[] as &[u64], 4, |_, item| *item, |_, _, _| {});
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn reject_duplicate_outputs_fails_later_jobs_with_the_same_output() {
+        let prepared = |input: &str, output: &str| {
+            Ok(PreparedJob {
+                job: TrimJob::new(input),
+                media_info: MediaInfo::parse(r#"{ "format": {} }"#).unwrap(),
+                segments: vec![(0.0, 10.0)],
+                output: PathBuf::from(output),
+            })
+        };
+
+        let result = reject_duplicate_outputs(vec![
+            prepared("a/clip.mp4", "out/clip_trim.mp4"),
+            Err(Error::InputFileDoesntExist),
+            prepared("b/clip.mp4", "out/clip_trim.mp4"),
+            prepared("c/other.mp4", "out/other_trim.mp4"),
+        ]);
+
+        let outputs = result
+            .into_iter()
+            .map(|prepared| prepared.map(|prepared| prepared.output))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            outputs,
+            vec![
+                Ok(PathBuf::from("out/clip_trim.mp4")),
+                Err(Error::InputFileDoesntExist),
+                Err(Error::DuplicateOutput("out/clip_trim.mp4".to_owned())),
+                Ok(PathBuf::from("out/other_trim.mp4")),
+            ]
+        );
+    }
+
+    #[test]
+    fn trim_result_duration_sums_segments() {
+        let result = TrimResult {
+            input: PathBuf::from("video.mp4"),
+            output: PathBuf::from("video_trim.mp4"),
+            segments: vec![(0.0, 10.0), (20.0, 25.5)],
+            input_duration: 100.0,
+        };
+        assert_eq!(result.duration(), 15.5);
+    }
+}
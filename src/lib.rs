@@ -0,0 +1,37 @@
+//! ttrim - Trim video files using ffmpeg.
+//!
+//! A [`TrimJob`] describes which parts of an input video to keep and where to write them,
+//! and a [`Trimmer`] runs it:
+//!
+//! ```no_run
+//! use ttrim::{Timestamp, TrimJob, Trimmer};
+//!
+//! let job = TrimJob::new("video.mp4")
+//!     .start(Timestamp::parse_timestamp("1:30").unwrap())
+//!     .end(Timestamp::parse_duration("45").unwrap())
+//!     .output("clip.mp4");
+//!
+//! let result = Trimmer::new().run(&job).unwrap();
+//! println!("Wrote {:.1}s to {}", result.duration(), result.output.display());
+//! ```
+
+pub mod detect;
+pub mod error;
+pub mod job;
+pub mod manifest;
+pub mod media_info;
+pub mod probe;
+pub mod range;
+pub mod timestamp;
+pub mod video_utils;
+
+pub use error::Error;
+pub use job::{
+    BlackOptions, JobFinished, JobProgress, PreparedJob, SilenceOptions, TrimJob, TrimResult,
+    Trimmer,
+};
+pub use manifest::{Manifest, ManifestRow};
+pub use media_info::MediaInfo;
+pub use range::TimeRange;
+pub use timestamp::{Snap, Timestamp, TimestampContext};
+pub use video_utils::{EncodeOptions, Progress, TrimMode};
@@ -1,77 +1,506 @@
 mod args;
-mod error;
-mod timestamp;
-mod video_utils;
+mod progress_bar;
 
 use clap::Parser;
 
-use args::Args;
-use error::Error;
-use timestamp::Timestamp;
-use video_utils::PathType;
+use args::{
+    Args, ChaptersArgs, Command, EncodeArgs, ProbeArgs, RunArgs, ScenesArgs, SplitArgs, TargetFile,
+};
+use progress_bar::{BatchProgress, ProgressBar};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::Arc;
+use ttrim::video_utils::{self, PathType, ProgressTracker, TrimInput};
+use ttrim::{detect, job, media_info, probe, range, timestamp};
+use ttrim::{
+    Error, JobFinished, Manifest, PreparedJob, Snap, TimestampContext, TrimResult, Trimmer,
+};
 
 fn main() {
-    if let Err(err) = trim() {
-        eprintln!("{}", err)
+    if let Err(err) = run() {
+        eprintln!("{}", err);
+        process::exit(1);
     }
 }
 
-fn trim() -> Result<(), Error> {
+fn run() -> Result<(), Error> {
     let args = Args::parse();
 
-    let target_file = args.target_file.clone();
+    match args.command {
+        Some(Command::Split(split_args)) => split(split_args),
+        Some(Command::Chapters(chapters_args)) => list_chapters(chapters_args),
+        Some(Command::Scenes(scenes_args)) => scenes(scenes_args),
+        Some(Command::Probe(probe_args)) => probe(probe_args),
+        Some(Command::Run(run_args)) => run_manifest(run_args),
+        None => trim(args),
+    }
+}
 
-    if !target_file.exists() {
-        Err(Error::InputFileDoesntExist)?
+fn trim(args: Args) -> Result<(), Error> {
+    let target_files = args.get_target_files()?;
+
+    if let [target_file] = target_files.as_slice() {
+        let target_file = match target_file {
+            TargetFile::File(target_file) => target_file,
+            TargetFile::Unmatched(pattern) => Err(Error::NoFilesMatched(pattern.clone()))?,
+        };
+        let result = trim_file(&args, target_file, ProgressBar::new(args.quiet))?;
+        println!(
+            "Successfully trimmed video. Output file: {}",
+            result.output.display()
+        );
+        return Ok(());
     }
 
-    if !video_utils::check_valid_file_extension(&target_file) {
-        Err(Error::InvalidExtension)?
+    if let Some(output) = &args.output {
+        if !output.is_dir() {
+            Err(Error::OutputNotDirectoryForBatch)?
+        }
     }
 
-    let start_timestamp = args.get_start_timestamp()?;
-    let mut end_timestamp = args.get_end_timestamp()?;
+    let jobs = target_files
+        .iter()
+        .filter_map(|target_file| match target_file {
+            TargetFile::File(target_file) => Some(args.get_trim_job(target_file)),
+            TargetFile::Unmatched(_) => None,
+        })
+        .collect::<Result<Vec<_>, _>>()?;
 
-    let duration = video_utils::get_video_length(&target_file)?;
+    // Every output is worked out before any job is run, as files with the same name in different
+    // directories would otherwise be trimmed to the same output
+    let trimmer = Trimmer::new();
+    let prepared_jobs =
+        job::reject_duplicate_outputs(jobs.iter().map(|job| trimmer.prepare(job)).collect());
+    let runnable_jobs = prepared_jobs
+        .iter()
+        .filter_map(|prepared| prepared.as_ref().ok().cloned())
+        .collect::<Vec<_>>();
 
-    if end_timestamp.is_before(&start_timestamp, duration) {
-        Err(Error::EndTimestampBeforeStartTimestamp)?
+    let batch_progress = Arc::new(BatchProgress::new(
+        ProgressBar::new(args.quiet),
+        runnable_jobs.len(),
+    ));
+    let results = get_batch_trimmer(&batch_progress).run_all_prepared(
+        &runnable_jobs,
+        args.jobs as usize,
+        |finished| batch_progress.finish(finished.index, || print_progress(finished)),
+    );
+    batch_progress.clear();
+
+    // Files which couldn't be run and patterns which matched nothing are reported alongside
+    // the files, in the order they were given
+    let mut results = results.into_iter();
+    let mut prepared_jobs = prepared_jobs.into_iter();
+    let results = target_files
+        .iter()
+        .map(|target_file| match target_file {
+            TargetFile::File(_) => match prepared_jobs.next().expect("a job for every file") {
+                Ok(_) => results.next().expect("a result for every job"),
+                Err(err) => Err(err),
+            },
+            TargetFile::Unmatched(pattern) => Err(Error::NoFilesMatched(pattern.clone())),
+        })
+        .collect::<Vec<_>>();
+    let target_files = target_files
+        .iter()
+        .map(TargetFile::path)
+        .collect::<Vec<_>>();
+
+    report_batch(&target_files, &results)
+}
+
+fn trim_file(
+    args: &Args,
+    target_file: &Path,
+    progress_bar: ProgressBar,
+) -> Result<TrimResult, Error> {
+    let job = args.get_trim_job(target_file)?;
+    let result = get_trimmer(progress_bar).run(&job);
+    progress_bar.clear();
+    let result = result?;
+
+    if job.jump_cut.is_some() {
+        println!(
+            "Jump cut into {} segments, keeping {} of {}",
+            result.segments.len(),
+            timestamp::format_seconds(result.duration()),
+            timestamp::format_seconds(result.input_duration)
+        );
+    } else if job.snap.is_some() || job.trim_black.is_some() || job.trim_silence {
+        for (start, end) in &result.segments {
+            println!(
+                "Kept: {} - {}",
+                timestamp::format_seconds(*start),
+                timestamp::format_seconds(*end)
+            );
+        }
     }
 
-    if end_timestamp.to_seconds(duration) > duration {
-        end_timestamp = Timestamp::End;
+    Ok(result)
+}
+
+fn run_manifest(args: RunArgs) -> Result<(), Error> {
+    let manifest = Manifest::read(&args.manifest)?;
+
+    if let Some(output_dir) = &args.output {
+        if !output_dir.is_dir() {
+            Err(Error::OutputNotDirectory)?
+        }
     }
 
-    let output_path = match args.output {
-        Some(mut path) => {
-            if path.is_dir() {
-                path.push(video_utils::generate_output_filename(
-                    &target_file,
-                    PathType::FileOnly,
-                ))
-            }
-            if path.exists() {
-                return Err(Error::OutputFileExists);
-            }
-            if !video_utils::check_valid_file_extension(&path) {
-                return Err(Error::InvalidExtension);
-            }
-            path
+    let trimmer = Trimmer::new();
+    let mode = args.encode.get_trim_mode();
+
+    // Every job is checked before any are run, so that a mistake in one row doesn't
+    // only show up after all of the rows before it have been trimmed
+    let prepared_jobs = manifest
+        .rows
+        .iter()
+        .enumerate()
+        .map(|(index, row)| {
+            let mut job = manifest.get_job(index + 1, row, args.output.as_deref())?;
+            job.mode = mode.clone();
+            job.snap = args.encode.snap;
+            trimmer.prepare(&job)
+        })
+        .collect::<Vec<Result<PreparedJob, Error>>>();
+    let prepared_jobs = job::reject_duplicate_outputs(prepared_jobs);
+
+    let total = prepared_jobs.len();
+    let invalid = prepared_jobs.iter().filter(|job| job.is_err()).count();
+
+    for (index, prepared) in prepared_jobs.iter().enumerate() {
+        if let Err(err) = prepared {
+            eprintln!("Row {}: {}", index + 1, err);
         }
-        None => video_utils::generate_output_filename(&target_file, PathType::Relative),
-    };
+    }
+
+    if invalid > 0 {
+        Err(Error::InvalidManifestJobs(invalid, total))?
+    }
+
+    let prepared_jobs = prepared_jobs.into_iter().collect::<Result<Vec<_>, _>>()?;
+
+    if args.dry_run {
+        print_dry_run(&prepared_jobs);
+        return Ok(());
+    }
 
-    video_utils::trim_video(
-        start_timestamp.to_seconds(duration),
-        end_timestamp.to_seconds(duration),
-        &target_file,
-        &output_path,
-    )?;
+    let batch_progress = Arc::new(BatchProgress::new(
+        ProgressBar::new(args.quiet),
+        prepared_jobs.len(),
+    ));
+    let results = get_batch_trimmer(&batch_progress).run_all_prepared(
+        &prepared_jobs,
+        args.jobs as usize,
+        |finished| batch_progress.finish(finished.index, || print_progress(finished)),
+    );
+    batch_progress.clear();
+
+    let target_files = prepared_jobs
+        .iter()
+        .map(|prepared| prepared.job.input.clone())
+        .collect::<Vec<_>>();
+
+    report_batch(&target_files, &results)
+}
+
+/// A trimmer which draws the progress of a single job on the bar.
+fn get_trimmer(progress_bar: ProgressBar) -> Trimmer {
+    if !progress_bar.is_enabled() {
+        return Trimmer::new();
+    }
+
+    Trimmer::new().on_progress(move |job| progress_bar.draw(None, &job.progress))
+}
+
+/// A trimmer which draws the combined progress of every job it runs on one bar.
+fn get_batch_trimmer(batch_progress: &Arc<BatchProgress>) -> Trimmer {
+    if !batch_progress.is_enabled() {
+        return Trimmer::new();
+    }
+
+    let batch_progress = Arc::clone(batch_progress);
+    Trimmer::new().on_progress(move |job| batch_progress.update(job.index, &job.progress))
+}
+
+fn print_dry_run(prepared_jobs: &[PreparedJob]) {
+    let mut total_duration = 0.0;
+
+    for (index, prepared) in prepared_jobs.iter().enumerate() {
+        let (start, end) = prepared.segments[0];
+        total_duration += end - start;
+        println!(
+            "{:>3}  {}  {} - {}  -> {}",
+            index + 1,
+            prepared.job.input.display(),
+            timestamp::format_seconds(start),
+            timestamp::format_seconds(end),
+            prepared.output.display()
+        );
+    }
 
     println!(
-        "Successfully trimmed video. Output file: {}",
-        output_path.display()
+        "{} jobs would be trimmed, {} of video in total",
+        prepared_jobs.len(),
+        timestamp::format_seconds(total_duration)
     );
+}
+
+/// Reports each file as soon as it has been trimmed. Failures are only reported once, along with
+/// their reasons, in the summary printed by `report_batch`.
+fn print_progress(finished: JobFinished) {
+    if let Ok(result) = finished.result {
+        println!(
+            "[{}/{}] Trimmed {} -> {}",
+            finished.finished,
+            finished.total,
+            finished.input.display(),
+            result.output.display()
+        );
+    }
+}
+
+/// Prints whether each file was trimmed, returning an error if any of them failed.
+fn report_batch(
+    target_files: &[PathBuf],
+    results: &[Result<TrimResult, Error>],
+) -> Result<(), Error> {
+    let failed = results.iter().filter(|result| result.is_err()).count();
+
+    println!();
+    println!(
+        "Trimmed {} of {} files",
+        results.len() - failed,
+        results.len()
+    );
+
+    for (target_file, result) in target_files.iter().zip(results) {
+        match result {
+            Ok(result) => println!(
+                "  ok      {} -> {}",
+                target_file.display(),
+                result.output.display()
+            ),
+            Err(err) => println!("  failed  {}: {}", target_file.display(), err),
+        }
+    }
+
+    if failed > 0 {
+        Err(Error::BatchFailed(failed, results.len()))?
+    }
 
     Ok(())
 }
+
+fn split(args: SplitArgs) -> Result<(), Error> {
+    let media_info = media_info::probe_input(&args.target_file)?;
+    let context = TimestampContext::from_media_info(&media_info)?;
+    let duration = context.video_length;
+
+    let segments = if args.by_chapter {
+        if context.chapters.is_empty() {
+            Err(Error::NoChapters)?
+        }
+        context
+            .chapters
+            .iter()
+            .map(|chapter| (chapter.start, chapter.end.min(duration)))
+            .collect()
+    } else {
+        match args.get_every_timestamp()? {
+            Some(every) => {
+                let chunk_length = every.to_seconds(&context)?;
+                if chunk_length <= 0.0 {
+                    Err(Error::InvalidTime(
+                        "Provided length of each part must be greater than 0".to_owned(),
+                    ))?
+                }
+                range::split_every(duration, chunk_length)
+            }
+            None => range::split_into_parts(duration, args.parts.unwrap_or(1)),
+        }
+    };
+
+    // Snapping can't be combined with splitting by chapter, so chapters always line up with their segments
+    let input = TrimInput::new(&args.target_file, &media_info);
+    let segments = snap_segments(segments, args.encode.snap, &input, duration)?;
+
+    write_parts(
+        &segments,
+        &input,
+        args.output.as_deref(),
+        &args.encode,
+        ProgressBar::new(args.quiet),
+        |part, total_parts| {
+            if args.by_chapter {
+                video_utils::generate_chapter_output_filename(
+                    &args.target_file,
+                    PathType::FileOnly,
+                    part,
+                    total_parts,
+                    context.chapters[part - 1].title.as_deref(),
+                )
+            } else {
+                video_utils::generate_numbered_output_filename(
+                    &args.target_file,
+                    PathType::FileOnly,
+                    part,
+                    total_parts,
+                )
+            }
+        },
+    )
+}
+
+fn scenes(args: ScenesArgs) -> Result<(), Error> {
+    let media_info = media_info::probe_input(&args.target_file)?;
+    let duration = media_info.duration()?;
+    let scene_changes = detect::detect_scenes(&args.target_file, args.threshold)?;
+    let segments = range::split_at(duration, &scene_changes);
+
+    if args.split {
+        let input = TrimInput::new(&args.target_file, &media_info);
+        let segments = snap_segments(segments, args.encode.snap, &input, duration)?;
+        return write_parts(
+            &segments,
+            &input,
+            args.output.as_deref(),
+            &args.encode,
+            ProgressBar::new(args.quiet),
+            |part, total_parts| {
+                video_utils::generate_numbered_output_filename(
+                    &args.target_file,
+                    PathType::FileOnly,
+                    part,
+                    total_parts,
+                )
+            },
+        );
+    }
+
+    // Printed as `START-END` so that each scene can be passed straight to `--keep` or `--cut`
+    for (index, (start, end)) in segments.iter().enumerate() {
+        println!(
+            "{:>3}  {}-{}",
+            index + 1,
+            timestamp::format_seconds(*start),
+            timestamp::format_seconds(*end)
+        );
+    }
+
+    Ok(())
+}
+
+/// Trims each segment into its own file in the output directory, named by `get_filename`
+/// from the part number and the total number of parts.
+fn write_parts(
+    segments: &[(f64, f64)],
+    input: &TrimInput,
+    output_dir: Option<&Path>,
+    encode: &EncodeArgs,
+    progress_bar: ProgressBar,
+    get_filename: impl Fn(usize, usize) -> PathBuf,
+) -> Result<(), Error> {
+    if let Some(output_dir) = output_dir {
+        if !output_dir.is_dir() {
+            Err(Error::OutputNotDirectory)?
+        }
+    }
+
+    let total_parts = segments.len();
+    let output_paths = (1..=total_parts)
+        .map(|part| get_part_output_path(output_dir, get_filename(part, total_parts)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mode = encode.get_trim_mode();
+    for ((start, end), output_path) in segments.iter().zip(&output_paths) {
+        let label = output_path.display().to_string();
+        let draw = |progress| progress_bar.draw(Some(&label), &progress);
+        let progress = ProgressTracker::new(end - start, &draw);
+        let result = video_utils::trim_video(*start, *end, input, output_path, &mode, &progress);
+        progress_bar.clear();
+        result?;
+        println!("Created part: {}", output_path.display());
+    }
+
+    println!("Successfully split video into {} parts", segments.len());
+
+    Ok(())
+}
+
+fn list_chapters(args: ChaptersArgs) -> Result<(), Error> {
+    let chapters = media_info::probe_input(&args.target_file)?.chapters;
+
+    if chapters.is_empty() {
+        println!("No chapters found in {}", args.target_file.display());
+    }
+
+    for (index, chapter) in chapters.iter().enumerate() {
+        println!(
+            "{:>3}  {} - {}  {}",
+            index + 1,
+            timestamp::format_seconds(chapter.start),
+            timestamp::format_seconds(chapter.end),
+            chapter.title.as_deref().unwrap_or("")
+        );
+    }
+
+    Ok(())
+}
+
+fn probe(args: ProbeArgs) -> Result<(), Error> {
+    let media_info = media_info::probe_input(&args.target_file)?;
+    let report = probe::probe(&args.target_file, &media_info)?;
+
+    if args.json {
+        let json = serde_json::to_string_pretty(&report).map_err(|err| {
+            Error::InvalidData(format!("Failed to write probe output as JSON: {}", err))
+        })?;
+        println!("{}", json);
+    } else {
+        println!("File:               {}", args.target_file.display());
+        print!("{}", report);
+    }
+
+    Ok(())
+}
+
+/// Moves the segments onto keyframes if a snap option was provided, reporting where
+/// each segment will actually start and end.
+fn snap_segments(
+    segments: Vec<(f64, f64)>,
+    snap: Option<Snap>,
+    input: &TrimInput,
+    duration: f64,
+) -> Result<Vec<(f64, f64)>, Error> {
+    let snap = match snap {
+        Some(snap) => snap,
+        None => return Ok(segments),
+    };
+
+    let snapped_segments = video_utils::snap_to_keyframes(&segments, snap, input, duration)?;
+
+    for (start, end) in &snapped_segments {
+        println!(
+            "Snapped to keyframes: {} - {}",
+            timestamp::format_seconds(*start),
+            timestamp::format_seconds(*end)
+        );
+    }
+
+    Ok(snapped_segments)
+}
+
+fn get_part_output_path(output_dir: Option<&Path>, filename: PathBuf) -> Result<PathBuf, Error> {
+    let path = match output_dir {
+        Some(dir) => dir.join(filename),
+        None => Path::new("./").join(filename),
+    };
+
+    if path.exists() {
+        return Err(Error::OutputFileExists);
+    }
+
+    Ok(path)
+}
@@ -0,0 +1,279 @@
+use crate::error::Error;
+use crate::job::TrimJob;
+use crate::timestamp::Timestamp;
+use crate::video_utils::{self, PathType};
+
+use serde::{Deserialize, Deserializer};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A list of trims to run, read from a CSV, JSON or YAML file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Manifest {
+    /// The directory of the manifest file, which relative paths in it are resolved from.
+    pub base_dir: PathBuf,
+    pub rows: Vec<ManifestRow>,
+}
+
+/// One trim in a manifest. CSV manifests have a header row naming these columns,
+/// JSON and YAML manifests are a list of objects with these fields.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ManifestRow {
+    pub file: PathBuf,
+    #[serde(default, deserialize_with = "optional_timestamp")]
+    pub start: Option<String>,
+    #[serde(default, deserialize_with = "optional_timestamp")]
+    pub end: Option<String>,
+    #[serde(default)]
+    pub output: Option<PathBuf>,
+}
+
+impl Manifest {
+    /// Reads a manifest, choosing the format from its file extension.
+    pub fn read(path: &Path) -> Result<Manifest, Error> {
+        let contents = fs::read_to_string(path).map_err(|err| {
+            Error::InvalidManifest(format!("Could not read {}: {}", path.display(), err))
+        })?;
+
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or("")
+            .to_ascii_lowercase();
+
+        let rows = match extension.as_str() {
+            "csv" => parse_csv(&contents)?,
+            "json" => parse_json(&contents)?,
+            "yaml" | "yml" => parse_yaml(&contents)?,
+            _ => Err(Error::InvalidManifest(
+                "Expected a .csv, .json or .yaml file".to_owned(),
+            ))?,
+        };
+
+        Ok(Manifest {
+            base_dir: path.parent().unwrap_or(Path::new("")).to_path_buf(),
+            rows,
+        })
+    }
+
+    /// Builds the job for a row, numbered from 1. Relative input paths are resolved from the manifest's
+    /// directory, as are output names unless an output directory is provided. Rows without an output
+    /// name are given one which includes the row number, so several clips can be cut from one file.
+    pub fn get_job(
+        &self,
+        row_number: usize,
+        row: &ManifestRow,
+        output_dir: Option<&Path>,
+    ) -> Result<TrimJob, Error> {
+        let mut job = TrimJob::new(self.base_dir.join(&row.file));
+
+        if let Some(start) = &row.start {
+            job = job.start(Timestamp::parse_start(start)?);
+        }
+
+        if let Some(end) = &row.end {
+            job = job.end(Timestamp::parse_timestamp(end)?);
+        }
+
+        let output = match &row.output {
+            Some(output) => output.clone(),
+            None => video_utils::generate_row_output_filename(
+                &row.file,
+                PathType::FileOnly,
+                row_number,
+                self.rows.len().max(row_number),
+            ),
+        };
+
+        Ok(job.output(output_dir.unwrap_or(&self.base_dir).join(output)))
+    }
+}
+
+fn parse_csv(contents: &str) -> Result<Vec<ManifestRow>, Error> {
+    csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(contents.as_bytes())
+        .deserialize()
+        .collect::<Result<Vec<ManifestRow>, _>>()
+        .map_err(|err| Error::InvalidManifest(err.to_string()))
+}
+
+fn parse_json(contents: &str) -> Result<Vec<ManifestRow>, Error> {
+    serde_json::from_str(contents).map_err(|err| Error::InvalidManifest(err.to_string()))
+}
+
+fn parse_yaml(contents: &str) -> Result<Vec<ManifestRow>, Error> {
+    serde_yaml::from_str(contents).map_err(|err| Error::InvalidManifest(err.to_string()))
+}
+
+/// Timestamps are usually written as strings, but a number of seconds can be written as a number
+/// in JSON and YAML. Blank timestamps are treated as missing.
+fn optional_timestamp<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StringOrNumber {
+        String(String),
+        Number(f64),
+    }
+
+    Ok(match Option::<StringOrNumber>::deserialize(deserializer)? {
+        Some(StringOrNumber::String(string)) => Some(string.trim().to_owned()),
+        Some(StringOrNumber::Number(number)) => Some(number.to_string()),
+        None => None,
+    }
+    .filter(|timestamp| !timestamp.is_empty()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(
+        file: &str,
+        start: Option<&str>,
+        end: Option<&str>,
+        output: Option<&str>,
+    ) -> ManifestRow {
+        ManifestRow {
+            file: PathBuf::from(file),
+            start: start.map(str::to_owned),
+            end: end.map(str::to_owned),
+            output: output.map(PathBuf::from),
+        }
+    }
+
+    #[test]
+    fn parse_csv_reads_rows_with_blank_cells() {
+        let contents = "file,start,end,output\n\
+                        intro.mp4, 00:10 ,01:30,intro_cut.mp4\n\
+                        interview.mkv,,50%,\n";
+        let result = parse_csv(contents);
+        let expected = vec![
+            row(
+                "intro.mp4",
+                Some("00:10"),
+                Some("01:30"),
+                Some("intro_cut.mp4"),
+            ),
+            row("interview.mkv", None, Some("50%"), None),
+        ];
+        assert_eq!(result, Ok(expected));
+    }
+
+    #[test]
+    fn parse_csv_fails_without_file_column() {
+        let contents = "start,end\n10,20\n";
+        let result = parse_csv(contents);
+        assert!(matches!(result, Err(Error::InvalidManifest(_))));
+    }
+
+    #[test]
+    fn parse_json_accepts_numbers() {
+        let contents = r#"[{ "file": "intro.mp4", "start": 10, "end": "1:30" }]"#;
+        let result = parse_json(contents);
+        let expected = vec![row("intro.mp4", Some("10"), Some("1:30"), None)];
+        assert_eq!(result, Ok(expected));
+    }
+
+    #[test]
+    fn parse_yaml_reads_rows() {
+        let contents = "- file: intro.mp4\n  start: 12.5\n  output: intro_cut.mp4\n\
+                        - file: interview.mkv\n  end: \"-30\"\n";
+        let result = parse_yaml(contents);
+        let expected = vec![
+            row("intro.mp4", Some("12.5"), None, Some("intro_cut.mp4")),
+            row("interview.mkv", None, Some("-30"), None),
+        ];
+        assert_eq!(result, Ok(expected));
+    }
+
+    #[test]
+    fn get_job_resolves_paths_from_manifest_directory() {
+        let manifest = Manifest {
+            base_dir: PathBuf::from("jobs"),
+            rows: Vec::new(),
+        };
+        let job = manifest
+            .get_job(
+                1,
+                &row("intro.mp4", Some("10"), None, Some("cut.mp4")),
+                None,
+            )
+            .unwrap();
+        assert_eq!(job.input, PathBuf::from("jobs/intro.mp4"));
+        assert_eq!(job.output, Some(PathBuf::from("jobs/cut.mp4")));
+        assert_eq!(job.start, Timestamp::Seconds(10.0));
+        assert_eq!(job.end, Timestamp::End);
+    }
+
+    #[test]
+    fn get_job_puts_outputs_in_output_directory() {
+        let manifest = Manifest {
+            base_dir: PathBuf::from("jobs"),
+            rows: Vec::new(),
+        };
+        let job = manifest
+            .get_job(
+                1,
+                &row("intro.mp4", None, None, Some("cut.mp4")),
+                Some(Path::new("out")),
+            )
+            .unwrap();
+        assert_eq!(job.output, Some(PathBuf::from("out/cut.mp4")));
+    }
+
+    #[test]
+    fn get_job_fails_with_invalid_timestamp() {
+        let manifest = Manifest {
+            base_dir: PathBuf::new(),
+            rows: Vec::new(),
+        };
+        let result = manifest.get_job(1, &row("intro.mp4", Some("1:3O"), None, None), None);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn get_job_fails_with_relative_start() {
+        let manifest = Manifest {
+            base_dir: PathBuf::new(),
+            rows: Vec::new(),
+        };
+        let result = manifest.get_job(1, &row("intro.mp4", Some("+10"), Some("15"), None), None);
+        assert!(matches!(result, Err(Error::InvalidTime(_))));
+    }
+
+    #[test]
+    fn get_job_names_unnamed_outputs_by_row_next_to_manifest() {
+        let rows = vec![
+            row("interview.mkv", Some("10"), Some("20"), None),
+            row("interview.mkv", Some("30"), Some("40"), None),
+        ];
+        let manifest = Manifest {
+            base_dir: PathBuf::from("jobs"),
+            rows: rows.clone(),
+        };
+
+        let outputs = rows
+            .iter()
+            .enumerate()
+            .map(|(index, row)| {
+                manifest
+                    .get_job(index + 1, row, None)
+                    .unwrap()
+                    .output
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        assert_ne!(outputs[0], outputs[1]);
+        for (output, row) in outputs.iter().zip(["row1", "row2"]) {
+            assert_eq!(output.parent(), Some(Path::new("jobs")));
+            assert!(output
+                .to_string_lossy()
+                .ends_with(&format!("_{}_trim.mkv", row)));
+        }
+    }
+}
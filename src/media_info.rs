@@ -0,0 +1,340 @@
+use crate::error::Error;
+use crate::timestamp::Timecode;
+use crate::video_utils;
+
+use serde::de::{self, Deserializer};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+use std::str;
+use std::str::FromStr;
+
+/// Everything ffprobe reports about a video which ttrim makes use of.
+/// Probed once per file, then shared by validation, timestamp resolution and naming.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct MediaInfo {
+    #[serde(default)]
+    pub format: Format,
+    #[serde(default)]
+    pub streams: Vec<Stream>,
+    #[serde(default)]
+    pub chapters: Vec<Chapter>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct Format {
+    pub format_name: Option<String>,
+    pub format_long_name: Option<String>,
+    /// When the first stream starts. MPEG-TS files usually start a second or two in.
+    #[serde(default, deserialize_with = "optional_number")]
+    pub start_time: Option<f64>,
+    #[serde(default, deserialize_with = "optional_number")]
+    pub duration: Option<f64>,
+    #[serde(default, deserialize_with = "optional_number")]
+    pub bit_rate: Option<u64>,
+    #[serde(default)]
+    pub tags: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct Stream {
+    #[serde(default)]
+    pub index: u64,
+    pub codec_type: Option<String>,
+    pub codec_name: Option<String>,
+    pub width: Option<u64>,
+    pub height: Option<u64>,
+    pub profile: Option<String>,
+    /// The codec level, which ffprobe reports as a negative number when it is unknown.
+    pub level: Option<i64>,
+    pub pix_fmt: Option<String>,
+    pub time_base: Option<String>,
+    pub r_frame_rate: Option<String>,
+    pub avg_frame_rate: Option<String>,
+    #[serde(default, deserialize_with = "optional_number")]
+    pub duration: Option<f64>,
+    #[serde(default, deserialize_with = "optional_number")]
+    pub bit_rate: Option<u64>,
+    pub channels: Option<u64>,
+    #[serde(default, deserialize_with = "optional_number")]
+    pub sample_rate: Option<u64>,
+    #[serde(default)]
+    pub tags: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Chapter {
+    #[serde(
+        default,
+        rename(deserialize = "tags"),
+        deserialize_with = "title_from_tags"
+    )]
+    pub title: Option<String>,
+    #[serde(rename(deserialize = "start_time"), deserialize_with = "number")]
+    pub start: f64,
+    #[serde(rename(deserialize = "end_time"), deserialize_with = "number")]
+    pub end: f64,
+}
+
+/// Checks that the input file exists and looks like a video, then probes it.
+pub fn probe_input(path: &Path) -> Result<MediaInfo, Error> {
+    if !path.exists() {
+        Err(Error::InputFileDoesntExist)?
+    }
+
+    if !video_utils::check_valid_file_extension(path) {
+        Err(Error::InvalidExtension)?
+    }
+
+    let media_info = MediaInfo::probe(path)?;
+
+    if !media_info
+        .streams
+        .iter()
+        .any(|stream| stream.is_video() || stream.is_audio())
+    {
+        Err(Error::NoMediaStreams)?
+    }
+
+    Ok(media_info)
+}
+
+impl MediaInfo {
+    /// Runs ffprobe over the video, reading its format, streams and chapters in one go.
+    pub fn probe(path: &Path) -> Result<MediaInfo, Error> {
+        if !video_utils::check_program_installed("ffprobe".to_owned()) {
+            Err(Error::FfprobeNotInstalled)?
+        }
+
+        let output = Command::new("ffprobe")
+            .arg("-v")
+            .arg("error")
+            .arg("-of")
+            .arg("json")
+            .arg("-show_format")
+            .arg("-show_streams")
+            .arg("-show_chapters")
+            .arg(path)
+            .output();
+
+        let output = match output {
+            Ok(output) => match output.status.success() {
+                true => output,
+                false => return Err(Error::FfpmegError)?,
+            },
+            Err(_) => return Err(Error::FfpmegError)?,
+        };
+
+        let json = str::from_utf8(&output.stdout).map_err(|_| {
+            Error::InvalidData("Failed to read ffprobe output to a UTF-8 string".to_owned())
+        })?;
+
+        MediaInfo::parse(json)
+    }
+
+    /// Parses the JSON written by ffprobe with `-of json -show_format -show_streams -show_chapters`.
+    pub fn parse(json: &str) -> Result<MediaInfo, Error> {
+        serde_json::from_str(json)
+            .map_err(|err| Error::InvalidData(format!("Failed to parse ffprobe output: {}", err)))
+    }
+
+    /// The length of the video in seconds. Some containers don't record a duration,
+    /// in which case the longest of the stream durations is used.
+    pub fn duration(&self) -> Result<f64, Error> {
+        self.format
+            .duration
+            .or_else(|| {
+                self.streams
+                    .iter()
+                    .filter_map(|stream| stream.duration)
+                    .reduce(f64::max)
+            })
+            .ok_or(Error::InvalidData(
+                "ffprobe did not report a duration for the video".to_owned(),
+            ))
+    }
+
+    /// The time the container starts at. Packet timestamps are offset by this, while ffmpeg's
+    /// `-ss`, chapters and filter output all count from the start of the video.
+    pub fn start_time(&self) -> f64 {
+        self.format.start_time.unwrap_or(0.0)
+    }
+
+    /// A human readable name for the container format, e.g. `QuickTime / MOV`.
+    pub fn container(&self) -> &str {
+        self.format
+            .format_long_name
+            .as_deref()
+            .or(self.format.format_name.as_deref())
+            .unwrap_or("unknown")
+    }
+
+    pub fn video_stream(&self) -> Option<&Stream> {
+        self.streams.iter().find(|stream| stream.is_video())
+    }
+
+    /// The frame rate of the first video stream, which may be fractional such as 30000/1001.
+    pub fn frame_rate(&self) -> Option<f64> {
+        self.video_stream()?.frame_rate()
+    }
+
+    /// The timecode of the first frame of the video if one is embedded in the file,
+    /// either on the container or on one of its streams.
+    pub fn start_timecode(&self) -> Option<Timecode> {
+        std::iter::once(&self.format.tags)
+            .chain(self.streams.iter().map(|stream| &stream.tags))
+            .filter_map(|tags| tags.get("timecode"))
+            .find_map(|timecode| Timecode::parse_timecode(timecode.trim()).ok())
+    }
+}
+
+impl Stream {
+    pub fn is_video(&self) -> bool {
+        self.codec_type.as_deref() == Some("video")
+    }
+
+    pub fn is_audio(&self) -> bool {
+        self.codec_type.as_deref() == Some("audio")
+    }
+
+    /// The real frame rate is used, the average is only used if that is unknown.
+    pub fn frame_rate(&self) -> Option<f64> {
+        [&self.r_frame_rate, &self.avg_frame_rate]
+            .into_iter()
+            .flatten()
+            .find_map(|frame_rate| video_utils::parse_frame_rate(frame_rate))
+    }
+}
+
+/// ffprobe writes most numbers as strings, and uses `N/A` when one is unknown,
+/// so both strings and numbers are accepted.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NumberOrString<T> {
+    Number(T),
+    String(String),
+}
+
+fn optional_number<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr + Deserialize<'de>,
+{
+    Ok(
+        match Option::<NumberOrString<T>>::deserialize(deserializer)? {
+            Some(NumberOrString::Number(number)) => Some(number),
+            Some(NumberOrString::String(string)) => string.parse().ok(),
+            None => None,
+        },
+    )
+}
+
+fn number<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr + Deserialize<'de>,
+{
+    optional_number(deserializer)?.ok_or_else(|| de::Error::custom("expected a number"))
+}
+
+fn title_from_tags<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let mut tags = HashMap::<String, String>::deserialize(deserializer)?;
+    Ok(tags.remove("title"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_reads_numbers_written_as_strings() {
+        let json = r#"{
+            "streams": [{ "index": 0, "codec_type": "audio", "sample_rate": "48000", "channels": 2 }],
+            "format": { "duration": "125.500000", "bit_rate": "N/A" }
+        }"#;
+        let result = MediaInfo::parse(json).unwrap();
+        assert_eq!(result.format.duration, Some(125.5));
+        assert_eq!(result.format.bit_rate, None);
+        assert_eq!(result.streams[0].sample_rate, Some(48000));
+        assert_eq!(result.streams[0].channels, Some(2));
+    }
+
+    #[test]
+    fn parse_reads_chapter_times_and_titles() {
+        let json = r#"{
+            "chapters": [
+                { "id": 0, "start_time": "0.000000", "end_time": "60.000000", "tags": { "title": "Intro, part 1" } },
+                { "id": 1, "start_time": "60.000000", "end_time": "125.500000" }
+            ]
+        }"#;
+        let result = MediaInfo::parse(json).unwrap();
+        let expected = vec![
+            Chapter {
+                title: Some("Intro, part 1".to_owned()),
+                start: 0.0,
+                end: 60.0,
+            },
+            Chapter {
+                title: None,
+                start: 60.0,
+                end: 125.5,
+            },
+        ];
+        assert_eq!(result.chapters, expected);
+    }
+
+    #[test]
+    fn parse_fails_with_invalid_json() {
+        let result = MediaInfo::parse("not json");
+        assert!(matches!(result, Err(Error::InvalidData(_))));
+    }
+
+    #[test]
+    fn duration_falls_back_to_stream_durations() {
+        let json = r#"{
+            "streams": [{ "duration": "99.5" }, { "duration": "100.25" }, {}],
+            "format": { "duration": "N/A" }
+        }"#;
+        let result = MediaInfo::parse(json).unwrap().duration();
+        assert_eq!(result, Ok(100.25));
+    }
+
+    #[test]
+    fn start_time_reads_format_start_time() {
+        let json = r#"{ "format": { "start_time": "1.400000" } }"#;
+        let result = MediaInfo::parse(json).unwrap().start_time();
+        assert_eq!(result, 1.4);
+    }
+
+    #[test]
+    fn duration_fails_without_any_durations() {
+        let result = MediaInfo::parse(r#"{ "format": {} }"#).unwrap().duration();
+        assert!(matches!(result, Err(Error::InvalidData(_))));
+    }
+
+    #[test]
+    fn frame_rate_uses_first_video_stream() {
+        let json = r#"{
+            "streams": [
+                { "codec_type": "audio", "r_frame_rate": "0/0" },
+                { "codec_type": "video", "r_frame_rate": "0/0", "avg_frame_rate": "30000/1001" }
+            ]
+        }"#;
+        let result = MediaInfo::parse(json).unwrap().frame_rate();
+        assert_eq!(result, Some(30000.0 / 1001.0));
+    }
+
+    #[test]
+    fn start_timecode_reads_stream_tags() {
+        let json = r#"{
+            "streams": [{ "codec_type": "data", "tags": { "timecode": "01:00:00;00" } }],
+            "format": { "tags": { "encoder": "Lavf60.3.100" } }
+        }"#;
+        let result = MediaInfo::parse(json).unwrap().start_timecode();
+        assert_eq!(result, Timecode::parse_timecode("01:00:00;00").ok());
+    }
+}
@@ -0,0 +1,259 @@
+use crate::error::Error;
+use crate::media_info::{Chapter, MediaInfo, Stream};
+use crate::timestamp;
+use crate::video_utils;
+
+use serde::Serialize;
+use std::fmt;
+use std::path::Path;
+
+/// A summary of a video file and its streams, as reported by ffprobe.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ProbeReport {
+    pub duration: f64,
+    pub container: String,
+    pub bit_rate: Option<u64>,
+    pub keyframe_interval: Option<f64>,
+    pub streams: Vec<StreamReport>,
+    pub chapters: Vec<Chapter>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct StreamReport {
+    pub index: u64,
+    pub kind: String,
+    pub codec: String,
+    pub width: Option<u64>,
+    pub height: Option<u64>,
+    pub frame_rate: Option<f64>,
+    pub bit_rate: Option<u64>,
+    pub channels: Option<u64>,
+    pub sample_rate: Option<u64>,
+}
+
+/// Summarises the format, streams and chapters of a probed video.
+/// The keyframe interval requires reading every packet of the video, so is only worked out
+/// when the video has a video stream.
+pub fn probe(path: &Path, media_info: &MediaInfo) -> Result<ProbeReport, Error> {
+    let mut report = build_report(media_info)?;
+
+    if media_info.video_stream().is_some() {
+        report.keyframe_interval =
+            keyframe_interval(&video_utils::get_keyframes(path, media_info.start_time())?);
+    }
+
+    Ok(report)
+}
+
+fn build_report(media_info: &MediaInfo) -> Result<ProbeReport, Error> {
+    Ok(ProbeReport {
+        duration: media_info.duration()?,
+        container: media_info.container().to_owned(),
+        bit_rate: media_info.format.bit_rate,
+        keyframe_interval: None,
+        streams: media_info.streams.iter().map(build_stream_report).collect(),
+        chapters: media_info.chapters.clone(),
+    })
+}
+
+fn build_stream_report(stream: &Stream) -> StreamReport {
+    StreamReport {
+        index: stream.index,
+        kind: stream.codec_type.as_deref().unwrap_or("unknown").to_owned(),
+        codec: stream.codec_name.as_deref().unwrap_or("unknown").to_owned(),
+        width: stream.width,
+        height: stream.height,
+        frame_rate: if stream.is_video() {
+            stream.frame_rate()
+        } else {
+            None
+        },
+        bit_rate: stream.bit_rate,
+        channels: stream.channels,
+        sample_rate: stream.sample_rate,
+    }
+}
+
+/// The average number of seconds between keyframes, if there are at least two.
+fn keyframe_interval(keyframes: &[f64]) -> Option<f64> {
+    match (keyframes.first(), keyframes.last()) {
+        (Some(first), Some(last)) if keyframes.len() > 1 => {
+            Some((last - first) / (keyframes.len() - 1) as f64)
+        }
+        _ => None,
+    }
+}
+
+impl fmt::Display for ProbeReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Container:          {}", self.container)?;
+        writeln!(
+            f,
+            "Duration:           {}",
+            timestamp::format_seconds(self.duration)
+        )?;
+        if let Some(bit_rate) = self.bit_rate {
+            writeln!(f, "Bitrate:            {} kb/s", bit_rate / 1000)?;
+        }
+        if let Some(keyframe_interval) = self.keyframe_interval {
+            writeln!(f, "Keyframe interval:  {:.3}s", keyframe_interval)?;
+        }
+
+        for stream in &self.streams {
+            writeln!(f, "{}", stream)?;
+        }
+
+        if !self.chapters.is_empty() {
+            writeln!(f, "Chapters:")?;
+        }
+        for (index, chapter) in self.chapters.iter().enumerate() {
+            writeln!(
+                f,
+                "{:>3}  {} - {}  {}",
+                index + 1,
+                timestamp::format_seconds(chapter.start),
+                timestamp::format_seconds(chapter.end),
+                chapter.title.as_deref().unwrap_or("")
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for StreamReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut details = vec![self.codec.clone()];
+
+        if let (Some(width), Some(height)) = (self.width, self.height) {
+            details.push(format!("{}x{}", width, height));
+        }
+        if let Some(frame_rate) = self.frame_rate {
+            details.push(format!("{:.3} fps", frame_rate));
+        }
+        if let Some(channels) = self.channels {
+            details.push(format!("{} channels", channels));
+        }
+        if let Some(sample_rate) = self.sample_rate {
+            details.push(format!("{} Hz", sample_rate));
+        }
+        if let Some(bit_rate) = self.bit_rate {
+            details.push(format!("{} kb/s", bit_rate / 1000));
+        }
+
+        write!(
+            f,
+            "Stream #{} ({}): {}",
+            self.index,
+            self.kind,
+            details.join(", ")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROBE_OUTPUT: &str = r#"{
+        "streams": [
+            {
+                "index": 0,
+                "codec_name": "h264",
+                "codec_type": "video",
+                "width": 1920,
+                "height": 1080,
+                "r_frame_rate": "30000/1001",
+                "avg_frame_rate": "30000/1001",
+                "bit_rate": "4996000"
+            },
+            {
+                "index": 1,
+                "codec_name": "aac",
+                "codec_type": "audio",
+                "sample_rate": "48000",
+                "channels": 2,
+                "r_frame_rate": "0/0",
+                "bit_rate": "128000"
+            }
+        ],
+        "chapters": [
+            {
+                "id": 0,
+                "start_time": "0.000000",
+                "end_time": "60.000000",
+                "tags": { "title": "Intro" }
+            }
+        ],
+        "format": {
+            "format_name": "mov,mp4,m4a,3gp,3g2,mj2",
+            "format_long_name": "QuickTime / MOV",
+            "duration": "125.500000",
+            "bit_rate": "5130000"
+        }
+    }"#;
+
+    #[test]
+    fn build_report_reads_format() {
+        let result = build_report(&MediaInfo::parse(PROBE_OUTPUT).unwrap()).unwrap();
+        assert_eq!(result.duration, 125.5);
+        assert_eq!(result.container, "QuickTime / MOV");
+        assert_eq!(result.bit_rate, Some(5130000));
+        assert_eq!(
+            result.chapters,
+            vec![Chapter {
+                title: Some("Intro".to_owned()),
+                start: 0.0,
+                end: 60.0,
+            }]
+        );
+    }
+
+    #[test]
+    fn build_report_reads_streams() {
+        let result = build_report(&MediaInfo::parse(PROBE_OUTPUT).unwrap()).unwrap();
+        let expected = vec![
+            StreamReport {
+                index: 0,
+                kind: "video".to_owned(),
+                codec: "h264".to_owned(),
+                width: Some(1920),
+                height: Some(1080),
+                frame_rate: Some(30000.0 / 1001.0),
+                bit_rate: Some(4996000),
+                channels: None,
+                sample_rate: None,
+            },
+            StreamReport {
+                index: 1,
+                kind: "audio".to_owned(),
+                codec: "aac".to_owned(),
+                width: None,
+                height: None,
+                frame_rate: None,
+                bit_rate: Some(128000),
+                channels: Some(2),
+                sample_rate: Some(48000),
+            },
+        ];
+        assert_eq!(result.streams, expected);
+    }
+
+    #[test]
+    fn build_report_fails_without_duration() {
+        let result = build_report(&MediaInfo::parse(r#"{ "format": {} }"#).unwrap());
+        assert!(matches!(result, Err(Error::InvalidData(_))));
+    }
+
+    #[test]
+    fn keyframe_interval_averages_gaps() {
+        let result = keyframe_interval(&[0.0, 2.0, 4.0, 7.0]);
+        assert_eq!(result, Some(7.0 / 3.0));
+    }
+
+    #[test]
+    fn keyframe_interval_needs_two_keyframes() {
+        let result = keyframe_interval(&[0.0]);
+        assert_eq!(result, None);
+    }
+}
@@ -0,0 +1,221 @@
+use ttrim::Progress;
+
+use std::io::{self, IsTerminal, Write};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const BAR_WIDTH: usize = 30;
+
+/// Draws the progress of a trim on a single line of stderr, redrawing it as ffmpeg reports progress.
+/// Nothing is drawn when quiet, or when stderr isn't a terminal so logs aren't filled with bars.
+#[derive(Debug, Clone, Copy)]
+pub struct ProgressBar {
+    enabled: bool,
+}
+
+impl ProgressBar {
+    pub fn new(quiet: bool) -> ProgressBar {
+        ProgressBar {
+            enabled: !quiet && io::stderr().is_terminal(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn draw(&self, label: Option<&str>, progress: &Progress) {
+        if !self.enabled {
+            return;
+        }
+
+        let line = match label {
+            Some(label) => format!("{}  {}", format_bar(progress), label),
+            None => format_bar(progress),
+        };
+
+        let mut stderr = io::stderr().lock();
+        let _ = write!(stderr, "\r\x1b[2K{}", line);
+        let _ = stderr.flush();
+    }
+
+    /// Removes the bar, so that whatever is printed next starts on a clean line.
+    pub fn clear(&self) {
+        if !self.enabled {
+            return;
+        }
+
+        let mut stderr = io::stderr().lock();
+        let _ = write!(stderr, "\r\x1b[2K");
+        let _ = stderr.flush();
+    }
+}
+
+/// Combines the progress of every job in a batch into one bar. Each job counts equally, whether
+/// it is running, finished or yet to start, so the bar only moves forwards as jobs start.
+#[derive(Debug)]
+pub struct BatchProgress {
+    bar: ProgressBar,
+    started: Instant,
+    state: Mutex<BatchState>,
+}
+
+#[derive(Debug)]
+struct BatchState {
+    /// How far through each job is, from 0 to 1, and how fast it is going if it is running.
+    jobs: Vec<(f64, Option<f64>)>,
+    finished: usize,
+}
+
+impl BatchProgress {
+    pub fn new(bar: ProgressBar, total_jobs: usize) -> BatchProgress {
+        BatchProgress {
+            bar,
+            started: Instant::now(),
+            state: Mutex::new(BatchState {
+                jobs: vec![(0.0, None); total_jobs],
+                finished: 0,
+            }),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.bar.is_enabled()
+    }
+
+    /// Records the progress of the job at `index` and redraws the bar.
+    pub fn update(&self, index: usize, progress: &Progress) {
+        let mut state = self.state.lock().unwrap();
+        state.jobs[index] = (progress.fraction(), progress.speed);
+        self.draw(&state);
+    }
+
+    /// Records that the job at `index` has finished, running `print` while the bar is cleared
+    /// so that whatever it prints isn't drawn over.
+    pub fn finish(&self, index: usize, print: impl FnOnce()) {
+        let mut state = self.state.lock().unwrap();
+        state.jobs[index] = (1.0, None);
+        state.finished += 1;
+
+        self.bar.clear();
+        print();
+        self.draw(&state);
+    }
+
+    pub fn clear(&self) {
+        let _state = self.state.lock().unwrap();
+        self.bar.clear();
+    }
+
+    fn draw(&self, state: &BatchState) {
+        let progress = combine_progress(&state.jobs, self.started.elapsed());
+        let label = format!("{}/{} files", state.finished, state.jobs.len());
+        self.bar.draw(Some(&label), &progress);
+    }
+}
+
+/// Adds up the progress of each job, counting each job as one unit of work. The speed is the
+/// combined speed of the running jobs.
+fn combine_progress(jobs: &[(f64, Option<f64>)], elapsed: Duration) -> Progress {
+    let speeds = jobs
+        .iter()
+        .filter_map(|(_, speed)| *speed)
+        .collect::<Vec<_>>();
+
+    Progress {
+        done: jobs.iter().map(|(fraction, _)| fraction).sum(),
+        total: jobs.len() as f64,
+        speed: (!speeds.is_empty()).then(|| speeds.iter().sum()),
+        elapsed,
+    }
+}
+
+/// Formats progress as e.g. `[#########---------------------]  30.0%  2.10x  ETA 00:01:05`.
+fn format_bar(progress: &Progress) -> String {
+    let fraction = progress.fraction();
+    let filled = ((fraction * BAR_WIDTH as f64).round() as usize).min(BAR_WIDTH);
+
+    let speed = match progress.speed {
+        Some(speed) => format!("{:.2}x", speed),
+        None => "-".to_owned(),
+    };
+
+    let eta = match progress.eta() {
+        Some(eta) => format_eta(eta),
+        None => "--:--:--".to_owned(),
+    };
+
+    format!(
+        "[{}{}] {:>5.1}%  {:>6}  ETA {}",
+        "#".repeat(filled),
+        "-".repeat(BAR_WIDTH - filled),
+        fraction * 100.0,
+        speed,
+        eta
+    )
+}
+
+fn format_eta(eta: Duration) -> String {
+    let seconds = eta.as_secs_f64().round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_bar_shows_percentage_speed_and_eta() {
+        let progress = Progress {
+            done: 30.0,
+            total: 100.0,
+            speed: Some(2.1),
+            elapsed: Duration::from_secs(28),
+        };
+        let result = format_bar(&progress);
+        assert_eq!(
+            result,
+            "[#########---------------------]  30.0%   2.10x  ETA 00:01:05"
+        );
+    }
+
+    #[test]
+    fn format_bar_works_before_ffmpeg_reports_progress() {
+        let progress = Progress {
+            done: 0.0,
+            total: 100.0,
+            speed: None,
+            elapsed: Duration::ZERO,
+        };
+        let result = format_bar(&progress);
+        assert_eq!(
+            result,
+            "[------------------------------]   0.0%       -  ETA --:--:--"
+        );
+    }
+
+    #[test]
+    fn combine_progress_counts_each_job_equally() {
+        let jobs = [(1.0, None), (0.5, Some(1.5)), (0.0, Some(2.0)), (0.0, None)];
+        let result = combine_progress(&jobs, Duration::from_secs(30));
+        let expected = Progress {
+            done: 1.5,
+            total: 4.0,
+            speed: Some(3.5),
+            elapsed: Duration::from_secs(30),
+        };
+        assert_eq!(result, expected);
+        assert_eq!(result.eta(), Some(Duration::from_secs(50)));
+    }
+
+    #[test]
+    fn format_eta_works_with_hours() {
+        let result = format_eta(Duration::from_secs(3 * 3600 + 25 * 60 + 7));
+        assert_eq!(result, "03:25:07");
+    }
+}
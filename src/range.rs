@@ -0,0 +1,363 @@
+use crate::error::Error;
+use crate::timestamp::{self, Snap, Timestamp, TimestampContext};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeRange {
+    pub start: Timestamp,
+    pub end: Timestamp,
+}
+
+impl TimeRange {
+    pub fn parse_range(range: &str) -> Result<TimeRange, Error> {
+        // The first character is skipped so that a start counted from the end, e.g. `-30--10`,
+        // isn't mistaken for the separator
+        let separator = range
+            .char_indices()
+            .skip(1)
+            .find(|(_, c)| *c == '-')
+            .map(|(index, _)| index)
+            .ok_or_else(|| Error::InvalidRange(range.to_owned()))?;
+        let (start, end) = (&range[..separator], &range[separator + 1..]);
+
+        if start.trim().is_empty() || end.trim().is_empty() {
+            return Err(Error::InvalidRange(range.to_owned()));
+        }
+
+        Ok(TimeRange {
+            start: Timestamp::parse_start(start.trim())?,
+            end: Timestamp::parse_timestamp(end.trim())?,
+        })
+    }
+
+    /// Resolves the range to a pair of start and end seconds, clamping the end to the video length.
+    /// A relative end is resolved from the start of the range.
+    pub fn to_seconds(&self, context: &TimestampContext) -> Result<(f64, f64), Error> {
+        let start = self.start.to_seconds(context)?;
+        let end = self.end.to_seconds(&context.relative_to(start))?;
+
+        if start >= context.video_length {
+            return Err(Error::InvalidTime(format!(
+                "Provided range {}-{} starts after the end of the video at {}",
+                timestamp::format_seconds(start),
+                timestamp::format_seconds(end),
+                timestamp::format_seconds(context.video_length)
+            )));
+        }
+
+        let end = end.min(context.video_length);
+
+        if start >= end {
+            return Err(Error::EndTimestampBeforeStartTimestamp);
+        }
+
+        Ok((start, end))
+    }
+}
+
+/// Resolves a list of ranges to seconds, sorted by start time.
+/// Returns an error if any of the ranges overlap.
+pub fn resolve_ranges(
+    ranges: &[TimeRange],
+    context: &TimestampContext,
+) -> Result<Vec<(f64, f64)>, Error> {
+    let mut segments = ranges
+        .iter()
+        .map(|range| range.to_seconds(context))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    segments.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    if segments.windows(2).any(|pair| pair[1].0 < pair[0].1) {
+        return Err(Error::OverlappingRanges);
+    }
+
+    Ok(segments)
+}
+
+/// Returns the parts of the video between 0 and the video length which are not covered by
+/// any of the given segments. The segments must be sorted and must not overlap.
+pub fn complement_segments(segments: &[(f64, f64)], video_length: f64) -> Vec<(f64, f64)> {
+    let mut kept = Vec::new();
+    let mut position = 0.0;
+
+    for (start, end) in segments {
+        if *start > position {
+            kept.push((position, *start));
+        }
+        position = end.max(position);
+    }
+
+    if position < video_length {
+        kept.push((position, video_length));
+    }
+
+    kept
+}
+
+/// Divides the video into the given number of equal length segments.
+pub fn split_into_parts(video_length: f64, parts: u32) -> Vec<(f64, f64)> {
+    let part_length = video_length / parts as f64;
+    (0..parts)
+        .map(|part| {
+            let start = part_length * part as f64;
+            let end = if part + 1 == parts {
+                video_length
+            } else {
+                part_length * (part + 1) as f64
+            };
+            (start, end)
+        })
+        .collect()
+}
+
+/// Divides the video into segments of the given length, the final segment holds whatever is left over.
+pub fn split_every(video_length: f64, chunk_length: f64) -> Vec<(f64, f64)> {
+    let mut segments = Vec::new();
+    let mut start = 0.0;
+
+    while start < video_length {
+        let end = (start + chunk_length).min(video_length);
+        segments.push((start, end));
+        start = end;
+    }
+
+    segments
+}
+
+/// Divides the video into segments at each of the given points, which must be in order.
+/// Points outside the video are ignored.
+pub fn split_at(video_length: f64, points: &[f64]) -> Vec<(f64, f64)> {
+    let mut segments = Vec::new();
+    let mut start = 0.0;
+
+    for point in points {
+        if *point > start && *point < video_length {
+            segments.push((start, *point));
+            start = *point;
+        }
+    }
+
+    segments.push((start, video_length));
+    segments
+}
+
+/// Moves the start and end of each segment onto a keyframe, leaving the very start and end
+/// of the video where they are. Segments which become empty after snapping are dropped.
+pub fn snap_segments(
+    segments: &[(f64, f64)],
+    snap: Snap,
+    keyframes: &[f64],
+    video_length: f64,
+) -> Vec<(f64, f64)> {
+    segments
+        .iter()
+        .map(|(start, end)| {
+            let start = if *start > 0.0 {
+                snap.snap_seconds(*start, keyframes)
+            } else {
+                *start
+            };
+            let end = if *end < video_length {
+                snap.snap_seconds(*end, keyframes)
+            } else {
+                *end
+            };
+            (start, end)
+        })
+        .filter(|(start, end)| start < end)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_range_works_with_seconds() {
+        let result = TimeRange::parse_range("10-90");
+        let expected = Ok(TimeRange {
+            start: Timestamp::Seconds(10.0),
+            end: Timestamp::Seconds(90.0),
+        });
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn parse_range_works_with_mixed_formats() {
+        let result = TimeRange::parse_range("04:00-50%");
+        let expected = Ok(TimeRange {
+            start: Timestamp::Seconds(240.0),
+            end: Timestamp::Percentage(50.0),
+        });
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn parse_range_works_with_bounds_from_end() {
+        let result = TimeRange::parse_range("-30--10");
+        let expected = Ok(TimeRange {
+            start: Timestamp::FromEnd(Box::new(Timestamp::Seconds(30.0))),
+            end: Timestamp::FromEnd(Box::new(Timestamp::Seconds(10.0))),
+        });
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn parse_range_fails_without_separator() {
+        let result = TimeRange::parse_range("10");
+        let expected = Err(Error::InvalidRange("10".to_owned()));
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn parse_range_fails_with_missing_bound() {
+        let result = TimeRange::parse_range("10-");
+        let expected = Err(Error::InvalidRange("10-".to_owned()));
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn parse_range_fails_with_relative_start() {
+        let result = TimeRange::parse_range("+10-20");
+        assert!(matches!(result, Err(Error::InvalidTime(_))));
+    }
+
+    #[test]
+    fn resolve_ranges_sorts_by_start() {
+        let ranges = vec![
+            TimeRange::parse_range("240-315").unwrap(),
+            TimeRange::parse_range("10-90").unwrap(),
+        ];
+        let result = resolve_ranges(&ranges, &TimestampContext::with_length(400.0));
+        let expected = Ok(vec![(10.0, 90.0), (240.0, 315.0)]);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn resolve_ranges_clamps_end_to_video_length() {
+        let ranges = vec![TimeRange::parse_range("10-500").unwrap()];
+        let result = resolve_ranges(&ranges, &TimestampContext::with_length(400.0));
+        let expected = Ok(vec![(10.0, 400.0)]);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn resolve_ranges_fails_with_range_after_end_of_video() {
+        let ranges = vec![TimeRange::parse_range("500-600").unwrap()];
+        let result = resolve_ranges(&ranges, &TimestampContext::with_length(400.0));
+        let expected = Err(Error::InvalidTime(
+            "Provided range 00:08:20.000-00:10:00.000 starts after the end of the video at 00:06:40.000"
+                .to_owned(),
+        ));
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn resolve_ranges_resolves_relative_end_from_range_start() {
+        let ranges = vec![TimeRange::parse_range("04:00-+75").unwrap()];
+        let result = resolve_ranges(&ranges, &TimestampContext::with_length(400.0));
+        let expected = Ok(vec![(240.0, 315.0)]);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn resolve_ranges_fails_with_overlapping_ranges() {
+        let ranges = vec![
+            TimeRange::parse_range("10-90").unwrap(),
+            TimeRange::parse_range("80-120").unwrap(),
+        ];
+        let result = resolve_ranges(&ranges, &TimestampContext::with_length(400.0));
+        let expected = Err(Error::OverlappingRanges);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn resolve_ranges_fails_with_reversed_range() {
+        let ranges = vec![TimeRange::parse_range("90-10").unwrap()];
+        let result = resolve_ranges(&ranges, &TimestampContext::with_length(400.0));
+        let expected = Err(Error::EndTimestampBeforeStartTimestamp);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn complement_segments_keeps_everything_outside_cuts() {
+        let segments = vec![(60.0, 105.0), (200.0, 250.0)];
+        let result = complement_segments(&segments, 400.0);
+        let expected = vec![(0.0, 60.0), (105.0, 200.0), (250.0, 400.0)];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn complement_segments_handles_cuts_at_the_edges() {
+        let segments = vec![(0.0, 30.0), (350.0, 400.0)];
+        let result = complement_segments(&segments, 400.0);
+        let expected = vec![(30.0, 350.0)];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn complement_segments_is_empty_when_everything_is_cut() {
+        let segments = vec![(0.0, 400.0)];
+        let result = complement_segments(&segments, 400.0);
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn split_into_parts_divides_evenly() {
+        let result = split_into_parts(300.0, 3);
+        let expected = vec![(0.0, 100.0), (100.0, 200.0), (200.0, 300.0)];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn split_into_parts_ends_at_video_length() {
+        let result = split_into_parts(100.0, 3);
+        assert_eq!(result.len(), 3);
+        assert_eq!(result.last().unwrap().1, 100.0);
+    }
+
+    #[test]
+    fn split_every_leaves_remainder_in_last_segment() {
+        let result = split_every(1500.0, 600.0);
+        let expected = vec![(0.0, 600.0), (600.0, 1200.0), (1200.0, 1500.0)];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn split_every_with_chunk_longer_than_video() {
+        let result = split_every(100.0, 600.0);
+        let expected = vec![(0.0, 100.0)];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn split_at_divides_at_each_point() {
+        let result = split_at(100.0, &[12.5, 40.0]);
+        let expected = vec![(0.0, 12.5), (12.5, 40.0), (40.0, 100.0)];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn split_at_ignores_points_outside_video() {
+        let result = split_at(100.0, &[0.0, 50.0, 100.0, 120.0]);
+        let expected = vec![(0.0, 50.0), (50.0, 100.0)];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn snap_segments_leaves_video_bounds_alone() {
+        let keyframes = [0.0, 2.0, 4.0, 6.0];
+        let segments = vec![(0.0, 3.0), (5.0, 7.0)];
+        let result = snap_segments(&segments, Snap::PrevKeyframe, &keyframes, 7.0);
+        let expected = vec![(0.0, 2.0), (4.0, 7.0)];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn snap_segments_drops_empty_segments() {
+        let keyframes = [0.0, 2.0, 4.0, 6.0];
+        let segments = vec![(2.5, 2.9)];
+        let result = snap_segments(&segments, Snap::Nearest, &keyframes, 7.0);
+        assert!(result.is_empty());
+    }
+}
@@ -1,80 +1,296 @@
 use crate::error::Error;
+use crate::media_info::{Chapter, MediaInfo};
 use regex::Regex;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Timestamp {
     Start,
     End,
     Seconds(f64),
-    Percentage(i32),
+    Percentage(f64),
+    Frame(u64),
+    Timecode(Timecode),
+    /// A timestamp relative to the start of the cut, such as `+45` for 45 seconds after the start.
+    Relative(Box<Timestamp>),
+    /// A timestamp counted back from the end of the video, such as `-30` for 30 seconds before the end.
+    FromEnd(Box<Timestamp>),
+    ChapterStart(ChapterRef),
+    ChapterEnd(ChapterRef),
+}
+
+/// Refers to one of the chapters of a video, either by its position starting from 1 or by its title.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChapterRef {
+    Index(usize),
+    Title(String),
+}
+
+impl ChapterRef {
+    pub fn parse_chapter_ref(chapter: &str) -> ChapterRef {
+        match chapter.parse() {
+            Ok(index) => ChapterRef::Index(index),
+            Err(_) => ChapterRef::Title(chapter.to_owned()),
+        }
+    }
+}
+
+/// Details of the video needed to resolve a timestamp to a number of seconds.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TimestampContext {
+    pub video_length: f64,
+    pub frame_rate: Option<f64>,
+    /// The timecode embedded in the video for its first frame, which timecodes are resolved relative to.
+    pub start_timecode: Option<Timecode>,
+    /// The point relative timestamps are resolved from, usually the start of the cut.
+    pub relative_start: f64,
+    pub chapters: Vec<Chapter>,
+}
+
+impl TimestampContext {
+    pub fn from_media_info(media_info: &MediaInfo) -> Result<TimestampContext, Error> {
+        Ok(TimestampContext {
+            video_length: media_info.duration()?,
+            // Only needed for frame number and timecode timestamps, so a missing frame rate is reported when one is used
+            frame_rate: media_info.frame_rate(),
+            start_timecode: media_info.start_timecode(),
+            relative_start: 0.0,
+            chapters: media_info.chapters.clone(),
+        })
+    }
+
+    /// A context for tests, for a video of the given length at 25fps.
+    #[cfg(test)]
+    pub(crate) fn with_length(video_length: f64) -> TimestampContext {
+        TimestampContext {
+            video_length,
+            frame_rate: Some(25.0),
+            ..Default::default()
+        }
+    }
+
+    /// Returns a copy of the context which resolves relative timestamps from the given number of seconds.
+    pub fn relative_to(&self, relative_start: f64) -> TimestampContext {
+        TimestampContext {
+            relative_start,
+            ..self.clone()
+        }
+    }
+
+    /// Finds a chapter by its position or by its title, ignoring case.
+    pub fn find_chapter(&self, chapter: &ChapterRef) -> Result<&Chapter, Error> {
+        let found = match chapter {
+            ChapterRef::Index(index) => index.checked_sub(1).and_then(|i| self.chapters.get(i)),
+            ChapterRef::Title(title) => self.chapters.iter().find(|chapter| {
+                chapter
+                    .title
+                    .as_ref()
+                    .is_some_and(|chapter_title| chapter_title.eq_ignore_ascii_case(title))
+            }),
+        };
+
+        found.ok_or_else(|| {
+            Error::ChapterNotFound(match chapter {
+                ChapterRef::Index(index) => index.to_string(),
+                ChapterRef::Title(title) => title.clone(),
+            })
+        })
+    }
+}
+
+/// A SMPTE timecode in the format `HH:MM:SS:FF`, or `HH:MM:SS;FF` for drop-frame timecode.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Timecode {
+    pub hours: u32,
+    pub minutes: u32,
+    pub seconds: u32,
+    pub frames: u32,
+    pub drop_frame: bool,
+}
+
+impl Timecode {
+    pub fn parse_timecode(timecode: &str) -> Result<Timecode, Error> {
+        if let Some(cap) = TimestampRegex::get_timecode_regex().captures(timecode) {
+            let hours = cap.get(1).map_or(0, |x| x.as_str().parse().unwrap());
+            let minutes = cap.get(2).map_or(0, |x| x.as_str().parse().unwrap());
+            let seconds = cap.get(3).map_or(0, |x| x.as_str().parse().unwrap());
+            let drop_frame = cap.get(4).is_some_and(|x| x.as_str() == ";");
+            let frames = cap.get(5).map_or(0, |x| x.as_str().parse().unwrap());
+
+            if minutes > 59 {
+                return Err(Error::InvalidTime(
+                    "Provided value for minutes cannot be greater than 60".to_owned(),
+                ));
+            }
+
+            if seconds > 59 {
+                return Err(Error::InvalidTime(
+                    "Provided value for seconds cannot be greater than 60".to_owned(),
+                ));
+            }
+
+            return Ok(Timecode {
+                hours,
+                minutes,
+                seconds,
+                frames,
+                drop_frame,
+            });
+        }
+        Err(Error::NoTimestampMatch)
+    }
+
+    /// Counts the number of frames from `00:00:00:00` to this timecode at the given frame rate.
+    /// Drop-frame timecode skips the first frame numbers of every minute except each tenth
+    /// minute, which keeps it in line with the clock at 29.97 and 59.94 fps, the only rates it is used at.
+    pub fn to_frame_count(self, frame_rate: f64) -> Result<u64, Error> {
+        let nominal_rate = frame_rate.round() as u64;
+        let frames = self.frames as u64;
+
+        if frames >= nominal_rate {
+            return Err(Error::InvalidTime(format!(
+                "Provided value for frames must be less than the frame rate of {}",
+                nominal_rate
+            )));
+        }
+
+        let total_minutes = (self.hours * 60 + self.minutes) as u64;
+        let total_seconds = total_minutes * 60 + self.seconds as u64;
+        let frame_count = total_seconds * nominal_rate + frames;
+
+        if !self.drop_frame {
+            return Ok(frame_count);
+        }
+
+        if nominal_rate != 30 && nominal_rate != 60 {
+            return Err(Error::InvalidTime(format!(
+                "Provided drop-frame timecode can only be used with 29.97 or 59.94 fps video, not {:.3} fps",
+                frame_rate
+            )));
+        }
+
+        let dropped_per_minute = nominal_rate / 15;
+        if self.seconds == 0 && !total_minutes.is_multiple_of(10) && frames < dropped_per_minute {
+            return Err(Error::InvalidTime(
+                "Provided drop-frame timecode uses a frame number which is dropped".to_owned(),
+            ));
+        }
+
+        Ok(frame_count - dropped_per_minute * (total_minutes - total_minutes / 10))
+    }
 }
 
 impl Timestamp {
     pub fn parse_timestamp(timestamp: &str) -> Result<Timestamp, Error> {
         match timestamp {
+            t if TimestampRegex::match_relative(t) => Timestamp::parse_relative(t),
+            t if TimestampRegex::match_from_end(t) => Timestamp::parse_from_end(t),
             t if TimestampRegex::match_seconds(t) => Timestamp::parse_seconds(t),
             t if TimestampRegex::match_hh_mm_ss(t) => Timestamp::parse_hh_mm_ss(t),
             t if TimestampRegex::match_percentage(t) => Timestamp::parse_percentage(t),
+            t if TimestampRegex::match_frame(t) => Timestamp::parse_frame(t),
+            t if TimestampRegex::match_timecode(t) => {
+                Timecode::parse_timecode(t).map(Timestamp::Timecode)
+            }
+            t if TimestampRegex::match_unit_duration(t) => Timestamp::parse_unit_duration(t),
             _ => Err(Error::NoTimestampMatch),
         }
     }
 
-    pub fn to_seconds(&self, video_length: f64) -> f64 {
-        match self {
-            Timestamp::Start => 0.0,
-            Timestamp::End => video_length,
-            Timestamp::Seconds(seconds) => *seconds,
-            Timestamp::Percentage(percentage) => (video_length * *percentage as f64) / 100.0,
+    /// Parses a start timestamp. Only the end can be relative to the start, so `+offset` is rejected.
+    pub fn parse_start(start: &str) -> Result<Timestamp, Error> {
+        match Timestamp::parse_timestamp(start)? {
+            Timestamp::Relative(_) => Err(Error::InvalidTime(format!(
+                "Provided start {} cannot be relative, only the end can be given as +offset",
+                start
+            ))),
+            timestamp => Ok(timestamp),
         }
     }
 
-    pub fn is_before(&self, other: &Timestamp, video_length: f64) -> bool {
+    /// Parses a length of time to keep after the start, which is always treated as relative to the start.
+    pub fn parse_duration(duration: &str) -> Result<Timestamp, Error> {
+        match Timestamp::parse_timestamp(duration)? {
+            relative @ Timestamp::Relative(_) => Ok(relative),
+            timestamp if timestamp.is_length() => Ok(Timestamp::Relative(Box::new(timestamp))),
+            _ => Err(Error::InvalidTime(format!(
+                "Provided duration {} must be a length of time rather than a point in the video",
+                duration
+            ))),
+        }
+    }
+
+    /// Whether the timestamp can be used as a length of time. Timestamps counted back from the end,
+    /// timecodes and chapters are only points in the video.
+    fn is_length(&self) -> bool {
+        matches!(
+            self,
+            Timestamp::Seconds(_) | Timestamp::Percentage(_) | Timestamp::Frame(_)
+        )
+    }
+
+    pub fn to_seconds(&self, context: &TimestampContext) -> Result<f64, Error> {
         match self {
-            Timestamp::Start => match other {
-                Timestamp::Start => false,
-                Timestamp::End => true,
-                Timestamp::Seconds(other_seconds) => *other_seconds > 0.0,
-                Timestamp::Percentage(other_percent) => *other_percent > 0,
+            Timestamp::Start => Ok(0.0),
+            Timestamp::End => Ok(context.video_length),
+            Timestamp::Seconds(seconds) => Ok(*seconds),
+            Timestamp::Percentage(percentage) => Ok((context.video_length * *percentage) / 100.0),
+            Timestamp::Frame(frame) => match context.frame_rate {
+                Some(frame_rate) => Ok(*frame as f64 / frame_rate),
+                None => Err(Error::UnknownFrameRate),
             },
-            Timestamp::End => match other {
-                Timestamp::End => false,
-                Timestamp::Start => false,
-                Timestamp::Seconds(other_seconds) => *other_seconds > video_length,
-                Timestamp::Percentage(other_percent) => *other_percent > 100,
-            },
-            Timestamp::Percentage(self_percent) => match other {
-                Timestamp::Start => false,
-                Timestamp::End => true,
-                Timestamp::Percentage(other_percent) => *other_percent > *self_percent,
-                Timestamp::Seconds(other_seconds) => {
-                    let self_seconds = (video_length * *self_percent as f64) / 100.0;
-                    *other_seconds > self_seconds
+            Timestamp::Timecode(timecode) => {
+                let frame_rate = context.frame_rate.ok_or(Error::UnknownFrameRate)?;
+                let frame_count = timecode.to_frame_count(frame_rate)?;
+                let start_frame_count = match &context.start_timecode {
+                    Some(start_timecode) => start_timecode.to_frame_count(frame_rate)?,
+                    None => 0,
+                };
+
+                if frame_count < start_frame_count {
+                    return Err(Error::InvalidTime(
+                        "Provided timecode is before the start timecode of the video".to_owned(),
+                    ));
                 }
-            },
 
-            Timestamp::Seconds(self_seconds) => match other {
-                Timestamp::Start => false,
-                Timestamp::End => *self_seconds < video_length,
-                Timestamp::Percentage(other_percent) => {
-                    let other_seconds = (video_length * *other_percent as f64) / 100.0;
-                    other_seconds > *self_seconds
+                Ok((frame_count - start_frame_count) as f64 / frame_rate)
+            }
+            Timestamp::Relative(offset) => Ok(context.relative_start + offset.to_seconds(context)?),
+            Timestamp::FromEnd(offset) => {
+                let offset = offset.to_seconds(context)?;
+                if offset > context.video_length {
+                    return Err(Error::InvalidTime(
+                        "Provided time to count back from the end is longer than the video"
+                            .to_owned(),
+                    ));
                 }
-                Timestamp::Seconds(other_seconds) => other_seconds > self_seconds,
-            },
+
+                Ok(context.video_length - offset)
+            }
+            Timestamp::ChapterStart(chapter) => Ok(context.find_chapter(chapter)?.start),
+            Timestamp::ChapterEnd(chapter) => Ok(context.find_chapter(chapter)?.end),
         }
     }
+
+    pub fn is_before(&self, other: &Timestamp, context: &TimestampContext) -> Result<bool, Error> {
+        Ok(self.to_seconds(context)? < other.to_seconds(context)?)
+    }
 }
 
 impl Timestamp {
     fn parse_percentage(timestamp: &str) -> Result<Timestamp, Error> {
         if let Some(cap) = TimestampRegex::get_percentage_regex().captures(timestamp) {
-            let percentage: i32 = cap.get(1).map(|x| x.as_str().parse().unwrap()).unwrap();
-            if percentage > 100 {
+            // Per-mille values are stored as a percentage so that both resolve the same way
+            let percentage: f64 = match (cap.get(1), cap.get(2)) {
+                (Some(percent), _) => parse_decimal(percent.as_str()).unwrap(),
+                (None, Some(per_mille)) => parse_decimal(per_mille.as_str()).unwrap() / 10.0,
+                (None, None) => unreachable!(),
+            };
+            if percentage > 100.0 {
                 return Err(Error::PercentageOutOfRange(
                     "Percentage cannot be greater than 100%".to_owned(),
                 ));
             }
-            if percentage < 0 {
+            if percentage < 0.0 {
                 return Err(Error::PercentageOutOfRange(
                     "Percentage cannot be lower than 0%".to_owned(),
                 ));
@@ -86,9 +302,11 @@ impl Timestamp {
 
     fn parse_hh_mm_ss(timestamp: &str) -> Result<Timestamp, Error> {
         if let Some(cap) = TimestampRegex::get_hh_mm_ss_regex().captures(timestamp) {
-            let hours = cap.get(1).map_or(0, |x| x.as_str().parse().unwrap());
-            let minutes = cap.get(2).map_or(0, |x| x.as_str().parse().unwrap());
-            let seconds = cap.get(3).map_or(0, |x| x.as_str().parse().unwrap());
+            let hours: u32 = cap.get(1).map_or(0, |x| x.as_str().parse().unwrap());
+            let minutes: u32 = cap.get(2).map_or(0, |x| x.as_str().parse().unwrap());
+            let seconds: f64 = cap
+                .get(3)
+                .map_or(0.0, |x| parse_decimal(x.as_str()).unwrap());
 
             if minutes > 59 {
                 return Err(Error::InvalidTime(
@@ -96,38 +314,211 @@ impl Timestamp {
                 ));
             }
 
-            if seconds > 59 {
+            if seconds >= 60.0 {
                 return Err(Error::InvalidTime(
                     "Provided value for seconds cannot be greater than 60".to_owned(),
                 ));
             }
 
-            let total_seconds = (hours * 60 * 60 + minutes * 60 + seconds) as f64;
+            let total_seconds = (hours * 60 * 60 + minutes * 60) as f64 + seconds;
             return Ok(Timestamp::Seconds(total_seconds));
         }
         Err(Error::NoTimestampMatch)
     }
 
+    fn parse_relative(timestamp: &str) -> Result<Timestamp, Error> {
+        if let Some(cap) = TimestampRegex::get_relative_regex().captures(timestamp) {
+            let offset = cap
+                .get(1)
+                .map(|x| Timestamp::parse_timestamp(x.as_str()))
+                .unwrap()?;
+
+            if !offset.is_length() {
+                return Err(Error::NoTimestampMatch);
+            }
+
+            return Ok(Timestamp::Relative(Box::new(offset)));
+        }
+        Err(Error::NoTimestampMatch)
+    }
+
+    fn parse_from_end(timestamp: &str) -> Result<Timestamp, Error> {
+        if let Some(cap) = TimestampRegex::get_from_end_regex().captures(timestamp) {
+            let offset = cap
+                .get(1)
+                .map(|x| Timestamp::parse_timestamp(x.as_str()))
+                .unwrap()?;
+
+            if matches!(offset, Timestamp::Relative(_) | Timestamp::FromEnd(_)) {
+                return Err(Error::NoTimestampMatch);
+            }
+
+            return Ok(Timestamp::FromEnd(Box::new(offset)));
+        }
+        Err(Error::NoTimestampMatch)
+    }
+
+    fn parse_frame(timestamp: &str) -> Result<Timestamp, Error> {
+        if let Some(cap) = TimestampRegex::get_frame_regex().captures(timestamp) {
+            let frame = cap
+                .get(1)
+                .or(cap.get(2))
+                .map(|x| x.as_str().parse().unwrap())
+                .unwrap();
+            return Ok(Timestamp::Frame(frame));
+        }
+        Err(Error::NoTimestampMatch)
+    }
+
     fn parse_seconds(timestamp: &str) -> Result<Timestamp, Error> {
-        if let Ok(seconds) = timestamp.parse::<f64>() {
+        if let Some(seconds) = parse_decimal(timestamp) {
             return Ok(Timestamp::Seconds(seconds));
         }
         Err(Error::NoTimestampMatch)
     }
+
+    /// Parses a duration made up of numbers followed by units, such as `1h30m`, `45s`, `1500ms` or `2.5min`.
+    /// Units must go from largest to smallest and can only be used once.
+    fn parse_unit_duration(timestamp: &str) -> Result<Timestamp, Error> {
+        let mut total_seconds = 0.0;
+        let mut parsed_length = 0;
+        let mut previous_unit_rank = None;
+
+        for cap in TimestampRegex::get_unit_duration_part_regex().captures_iter(timestamp) {
+            let part = cap.get(0).unwrap();
+            if part.start() != parsed_length {
+                break;
+            }
+            parsed_length = part.end();
+
+            let value = cap.get(1).and_then(|x| parse_decimal(x.as_str())).unwrap();
+            let unit = cap.get(2).unwrap().as_str();
+
+            let (unit_rank, unit_seconds) = match unit {
+                "h" => (0, 60.0 * 60.0),
+                "m" | "min" => (1, 60.0),
+                "s" => (2, 1.0),
+                "ms" => (3, 0.001),
+                _ => {
+                    return Err(Error::InvalidTime(format!(
+                        "Unknown unit `{}` in `{}`, expected one of h, m, min, s or ms",
+                        unit, timestamp
+                    )))
+                }
+            };
+
+            if previous_unit_rank.is_some_and(|previous| unit_rank <= previous) {
+                return Err(Error::InvalidTime(format!(
+                    "Units in `{}` must go from largest to smallest and can only be used once",
+                    timestamp
+                )));
+            }
+            previous_unit_rank = Some(unit_rank);
+
+            total_seconds += value * unit_seconds;
+        }
+
+        if parsed_length != timestamp.len() {
+            return Err(Error::InvalidTime(format!(
+                "Could not parse `{}`, expected numbers followed by units such as 1h30m or 45s",
+                timestamp
+            )));
+        }
+
+        Ok(Timestamp::Seconds(total_seconds))
+    }
+}
+
+/// Formats a number of seconds as `HH:MM:SS.mmm`, which can be parsed back into a timestamp.
+pub fn format_seconds(seconds: f64) -> String {
+    let total_millis = (seconds.max(0.0) * 1000.0).round() as u64;
+    let hours = total_millis / (60 * 60 * 1000);
+    let minutes = total_millis / (60 * 1000) % 60;
+    let millis = total_millis % (60 * 1000);
+
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        hours,
+        minutes,
+        millis / 1000,
+        millis % 1000
+    )
+}
+
+/// Parses a decimal number which may use either a `.` or a `,` (as in SRT subtitles) as the decimal separator.
+fn parse_decimal(value: &str) -> Option<f64> {
+    value.replace(',', ".").parse().ok()
+}
+
+/// Which keyframe a timestamp should be moved onto when cutting without re-encoding.
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum Snap {
+    /// The last keyframe at or before the timestamp.
+    PrevKeyframe,
+    /// The first keyframe at or after the timestamp.
+    NextKeyframe,
+    /// Whichever keyframe is closest to the timestamp.
+    Nearest,
+}
+
+impl Snap {
+    /// Moves the given number of seconds onto a keyframe from the provided sorted list.
+    /// If there is no keyframe in the requested direction the closest one is used instead.
+    pub fn snap_seconds(&self, seconds: f64, keyframes: &[f64]) -> f64 {
+        let prev = keyframes[..keyframes.partition_point(|keyframe| *keyframe <= seconds)].last();
+        let next = keyframes.get(keyframes.partition_point(|keyframe| *keyframe < seconds));
+
+        let snapped = match self {
+            Snap::PrevKeyframe => prev.or(next),
+            Snap::NextKeyframe => next.or(prev),
+            Snap::Nearest => match (prev, next) {
+                (Some(prev), Some(next)) if seconds - prev <= next - seconds => Some(prev),
+                (Some(_), Some(next)) => Some(next),
+                (prev, next) => prev.or(next),
+            },
+        };
+
+        snapped.copied().unwrap_or(seconds)
+    }
 }
 
 struct TimestampRegex;
 impl TimestampRegex {
     fn get_hh_mm_ss_regex() -> Regex {
-        Regex::new(r"^(?:(\d{1,2}):)?(\d{1,2}):(\d{1,2})$").unwrap()
+        Regex::new(r"^(?:(\d{1,2}):)?(\d{1,2}):(\d{1,2}(?:[.,]\d{1,9})?)$").unwrap()
     }
 
     fn get_seconds_regex() -> Regex {
-        Regex::new(r"^(\d{1,9})$").unwrap()
+        Regex::new(r"^(\d{1,9}(?:[.,]\d{1,9})?)$").unwrap()
     }
 
     fn get_percentage_regex() -> Regex {
-        Regex::new(r"^(\d{1,3})%").unwrap()
+        Regex::new(r"^(?:(\d{1,3}(?:[.,]\d{1,9})?)%|(\d{1,4}(?:[.,]\d{1,9})?)‰)$").unwrap()
+    }
+
+    fn get_frame_regex() -> Regex {
+        Regex::new(r"^(?:f(\d{1,12})|(\d{1,12})f)$").unwrap()
+    }
+
+    fn get_timecode_regex() -> Regex {
+        Regex::new(r"^(\d{1,2}):(\d{1,2}):(\d{1,2})([:;])(\d{1,3})$").unwrap()
+    }
+
+    fn get_relative_regex() -> Regex {
+        Regex::new(r"^\+(.+)$").unwrap()
+    }
+
+    fn get_from_end_regex() -> Regex {
+        Regex::new(r"^-(.+)$").unwrap()
+    }
+
+    // Deliberately loose, so that anything resembling a unit duration gets a helpful error from the parser
+    fn get_unit_duration_regex() -> Regex {
+        Regex::new(r"^\d[\d.,]*[a-z][\da-z.,]*$").unwrap()
+    }
+
+    fn get_unit_duration_part_regex() -> Regex {
+        Regex::new(r"(\d+(?:[.,]\d+)?)([a-z]+)").unwrap()
     }
 
     fn match_hh_mm_ss(timestamp: &str) -> bool {
@@ -141,6 +532,26 @@ impl TimestampRegex {
     fn match_percentage(timestamp: &str) -> bool {
         TimestampRegex::get_percentage_regex().is_match(timestamp)
     }
+
+    fn match_frame(timestamp: &str) -> bool {
+        TimestampRegex::get_frame_regex().is_match(timestamp)
+    }
+
+    fn match_timecode(timestamp: &str) -> bool {
+        TimestampRegex::get_timecode_regex().is_match(timestamp)
+    }
+
+    fn match_relative(timestamp: &str) -> bool {
+        TimestampRegex::get_relative_regex().is_match(timestamp)
+    }
+
+    fn match_from_end(timestamp: &str) -> bool {
+        TimestampRegex::get_from_end_regex().is_match(timestamp)
+    }
+
+    fn match_unit_duration(timestamp: &str) -> bool {
+        TimestampRegex::get_unit_duration_regex().is_match(timestamp)
+    }
 }
 
 #[cfg(test)]
@@ -163,11 +574,63 @@ mod tests {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn parse_seconds_works_with_fractional_seconds() {
+        let test_timestamp = "12.5";
+        let expected = Ok(Timestamp::Seconds(12.5));
+        let result = Timestamp::parse_seconds(test_timestamp);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn parse_seconds_works_with_comma_separator() {
+        let test_timestamp = "12,250";
+        let expected = Ok(Timestamp::Seconds(12.25));
+        let result = Timestamp::parse_seconds(test_timestamp);
+        assert_eq!(result, expected);
+    }
+
     #[test]
     fn parse_percentage_works_with_valid_input() {
         let test_timestamp = "50%";
         let result = Timestamp::parse_percentage(test_timestamp);
-        let expected = Ok(Timestamp::Percentage(50));
+        let expected = Ok(Timestamp::Percentage(50.0));
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn parse_percentage_works_with_fractional_percentage() {
+        let test_timestamp = "33.3%";
+        let result = Timestamp::parse_percentage(test_timestamp);
+        let expected = Ok(Timestamp::Percentage(33.3));
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn parse_percentage_works_with_per_mille() {
+        let test_timestamp = "333‰";
+        let result = Timestamp::parse_percentage(test_timestamp);
+        let expected = Ok(Timestamp::Percentage(33.3));
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn parse_percentage_fails_with_fractional_percentage_higher_than_100() {
+        let test_timestamp = "100.5%";
+        let result = Timestamp::parse_percentage(test_timestamp);
+        let expected = Err(Error::PercentageOutOfRange(
+            "Percentage cannot be greater than 100%".to_owned(),
+        ));
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn parse_percentage_fails_with_per_mille_higher_than_1000() {
+        let test_timestamp = "1001‰";
+        let result = Timestamp::parse_percentage(test_timestamp);
+        let expected = Err(Error::PercentageOutOfRange(
+            "Percentage cannot be greater than 100%".to_owned(),
+        ));
         assert_eq!(result, expected);
     }
 
@@ -219,6 +682,34 @@ mod tests {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn parse_hh_mm_ss_works_with_milliseconds() {
+        let test_timestamp = "1:23:45.678";
+        let expected_seconds = hh_mm_ss_to_seconds(1, 23, 45) + 0.678;
+        let result = Timestamp::parse_hh_mm_ss(test_timestamp);
+        let expected = Ok(Timestamp::Seconds(expected_seconds));
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn parse_hh_mm_ss_works_with_srt_style_milliseconds() {
+        let test_timestamp = "00:23:45,500";
+        let expected_seconds = hh_mm_ss_to_seconds(0, 23, 45) + 0.5;
+        let result = Timestamp::parse_hh_mm_ss(test_timestamp);
+        let expected = Ok(Timestamp::Seconds(expected_seconds));
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn parse_hh_mm_ss_fails_with_fractional_seconds_over_60() {
+        let test_timestamp = "45:60.5";
+        let result = Timestamp::parse_hh_mm_ss(test_timestamp);
+        let expected = Err(Error::InvalidTime(
+            "Provided value for seconds cannot be greater than 60".to_owned(),
+        ));
+        assert_eq!(result, expected);
+    }
+
     #[test]
     fn parse_hh_mm_ss_fails_with_invalid_minutes() {
         let test_timestamp = "60:45";
@@ -263,10 +754,208 @@ mod tests {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn parse_timestamp_works_with_fractional_seconds() {
+        let test_timestamp = "12.5";
+        let expected = Ok(Timestamp::Seconds(12.5));
+        let result = Timestamp::parse_timestamp(test_timestamp);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn parse_timestamp_works_with_mm_ss_milliseconds() {
+        let test_timestamp = "01:24.250";
+        let expected = Ok(Timestamp::Seconds(84.25));
+        let result = Timestamp::parse_timestamp(test_timestamp);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn parse_timestamp_fails_with_trailing_decimal_point() {
+        let test_timestamp = "12.";
+        let expected = Err(Error::NoTimestampMatch);
+        let result = Timestamp::parse_timestamp(test_timestamp);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn parse_timestamp_works_with_frame_prefix() {
+        let test_timestamp = "f1234";
+        let expected = Ok(Timestamp::Frame(1234));
+        let result = Timestamp::parse_timestamp(test_timestamp);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn parse_timestamp_works_with_frame_suffix() {
+        let test_timestamp = "1234f";
+        let expected = Ok(Timestamp::Frame(1234));
+        let result = Timestamp::parse_timestamp(test_timestamp);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn parse_timestamp_works_with_relative_seconds() {
+        let test_timestamp = "+45";
+        let expected = Ok(Timestamp::Relative(Box::new(Timestamp::Seconds(45.0))));
+        let result = Timestamp::parse_timestamp(test_timestamp);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn parse_timestamp_works_with_relative_mm_ss() {
+        let test_timestamp = "+1:30";
+        let expected = Ok(Timestamp::Relative(Box::new(Timestamp::Seconds(90.0))));
+        let result = Timestamp::parse_timestamp(test_timestamp);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn parse_timestamp_fails_with_nested_relative() {
+        let test_timestamp = "++45";
+        let expected = Err(Error::NoTimestampMatch);
+        let result = Timestamp::parse_timestamp(test_timestamp);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn parse_timestamp_works_with_negative_seconds() {
+        let test_timestamp = "-30";
+        let expected = Ok(Timestamp::FromEnd(Box::new(Timestamp::Seconds(30.0))));
+        let result = Timestamp::parse_timestamp(test_timestamp);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn parse_timestamp_works_with_negative_mm_ss() {
+        let test_timestamp = "-0:10";
+        let expected = Ok(Timestamp::FromEnd(Box::new(Timestamp::Seconds(10.0))));
+        let result = Timestamp::parse_timestamp(test_timestamp);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn parse_timestamp_fails_with_negative_relative() {
+        let test_timestamp = "-+30";
+        let expected = Err(Error::NoTimestampMatch);
+        let result = Timestamp::parse_timestamp(test_timestamp);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn parse_duration_makes_timestamp_relative() {
+        let expected = Ok(Timestamp::Relative(Box::new(Timestamp::Seconds(45.0))));
+        assert_eq!(Timestamp::parse_duration("45"), expected);
+        assert_eq!(Timestamp::parse_duration("+45"), expected);
+    }
+
+    #[test]
+    fn parse_duration_fails_with_points_in_the_video() {
+        for duration in ["-10", "01:00:00:00", "01:00:00;02"] {
+            let result = Timestamp::parse_duration(duration);
+            assert!(
+                matches!(result, Err(Error::InvalidTime(_))),
+                "{} was parsed as {:?}",
+                duration,
+                result
+            );
+        }
+    }
+
+    #[test]
+    fn parse_timestamp_fails_with_relative_timecode() {
+        let result = Timestamp::parse_timestamp("+01:00:00:00");
+        assert_eq!(result, Err(Error::NoTimestampMatch));
+    }
+
+    #[test]
+    fn parse_start_fails_with_relative_start() {
+        let result = Timestamp::parse_start("+10");
+        assert!(matches!(result, Err(Error::InvalidTime(_))));
+        assert_eq!(Timestamp::parse_start("10"), Ok(Timestamp::Seconds(10.0)));
+    }
+
+    #[test]
+    fn parse_timestamp_works_with_unit_duration() {
+        let test_timestamp = "1h2m3s";
+        let expected = Ok(Timestamp::Seconds(hh_mm_ss_to_seconds(1, 2, 3)));
+        let result = Timestamp::parse_timestamp(test_timestamp);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn parse_timestamp_works_with_single_unit_durations() {
+        assert_eq!(
+            Timestamp::parse_timestamp("90s"),
+            Ok(Timestamp::Seconds(90.0))
+        );
+        assert_eq!(
+            Timestamp::parse_timestamp("2.5m"),
+            Ok(Timestamp::Seconds(150.0))
+        );
+        assert_eq!(
+            Timestamp::parse_timestamp("2.5min"),
+            Ok(Timestamp::Seconds(150.0))
+        );
+        assert_eq!(
+            Timestamp::parse_timestamp("1500ms"),
+            Ok(Timestamp::Seconds(1.5))
+        );
+    }
+
+    #[test]
+    fn parse_timestamp_works_with_relative_unit_duration() {
+        let test_timestamp = "+1h30m";
+        let expected = Ok(Timestamp::Relative(Box::new(Timestamp::Seconds(5400.0))));
+        let result = Timestamp::parse_timestamp(test_timestamp);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn parse_timestamp_fails_with_unknown_unit() {
+        let test_timestamp = "1h2x";
+        let expected = Err(Error::InvalidTime(
+            "Unknown unit `x` in `1h2x`, expected one of h, m, min, s or ms".to_owned(),
+        ));
+        let result = Timestamp::parse_timestamp(test_timestamp);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn parse_timestamp_fails_with_units_out_of_order() {
+        let test_timestamp = "3s2m";
+        let expected = Err(Error::InvalidTime(
+            "Units in `3s2m` must go from largest to smallest and can only be used once".to_owned(),
+        ));
+        let result = Timestamp::parse_timestamp(test_timestamp);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn parse_timestamp_fails_with_repeated_unit() {
+        let test_timestamp = "1m1m";
+        let expected = Err(Error::InvalidTime(
+            "Units in `1m1m` must go from largest to smallest and can only be used once".to_owned(),
+        ));
+        let result = Timestamp::parse_timestamp(test_timestamp);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn parse_timestamp_fails_with_number_missing_unit() {
+        let test_timestamp = "1h30";
+        let expected = Err(Error::InvalidTime(
+            "Could not parse `1h30`, expected numbers followed by units such as 1h30m or 45s"
+                .to_owned(),
+        ));
+        let result = Timestamp::parse_timestamp(test_timestamp);
+        assert_eq!(result, expected);
+    }
+
     #[test]
     fn parse_timestamp_works_with_valid_percentage() {
         let test_timestamp = "100%";
-        let expected = Ok(Timestamp::Percentage(100));
+        let expected = Ok(Timestamp::Percentage(100.0));
         let result = Timestamp::parse_timestamp(test_timestamp);
         assert_eq!(result, expected);
     }
@@ -294,8 +983,10 @@ mod tests {
         let start = Timestamp::Start;
         let end = Timestamp::End;
 
-        let result = start.is_before(&end, 100.0);
-        assert_eq!(result, true);
+        let result = start
+            .is_before(&end, &TimestampContext::with_length(100.0))
+            .unwrap();
+        assert!(result);
     }
 
     #[test]
@@ -303,8 +994,10 @@ mod tests {
         let start = Timestamp::Start;
         let end = Timestamp::End;
 
-        let result = end.is_before(&start, 100.0);
-        assert_eq!(result, false);
+        let result = end
+            .is_before(&start, &TimestampContext::with_length(100.0))
+            .unwrap();
+        assert!(!result);
     }
 
     #[test]
@@ -312,9 +1005,11 @@ mod tests {
         let start = Timestamp::Start;
         let seconds = Timestamp::Seconds(10.0);
 
-        let result = start.is_before(&seconds, 100.0);
+        let result = start
+            .is_before(&seconds, &TimestampContext::with_length(100.0))
+            .unwrap();
         println!("{}", result);
-        assert_eq!(result, true);
+        assert!(result);
     }
 
     #[test]
@@ -322,9 +1017,11 @@ mod tests {
         let start = Timestamp::Start;
         let seconds = Timestamp::Seconds(10.0);
 
-        let result = seconds.is_before(&start, 100.0);
+        let result = seconds
+            .is_before(&start, &TimestampContext::with_length(100.0))
+            .unwrap();
         println!("{}", result);
-        assert_eq!(result, false);
+        assert!(!result);
     }
 
     #[test]
@@ -332,9 +1029,11 @@ mod tests {
         let seconds = Timestamp::Seconds(10.0);
         let end = Timestamp::End;
 
-        let result = seconds.is_before(&end, 100.0);
+        let result = seconds
+            .is_before(&end, &TimestampContext::with_length(100.0))
+            .unwrap();
         println!("{}", result);
-        assert_eq!(result, true);
+        assert!(result);
     }
 
     #[test]
@@ -342,55 +1041,462 @@ mod tests {
         let seconds = Timestamp::Seconds(101.0);
         let end = Timestamp::End;
 
-        let result = seconds.is_before(&end, 100.0);
-        assert_eq!(result, false);
+        let result = seconds
+            .is_before(&end, &TimestampContext::with_length(100.0))
+            .unwrap();
+        assert!(!result);
     }
 
     #[test]
     fn is_before_percentage_is_after_start() {
-        let percentage = Timestamp::Percentage(10);
+        let percentage = Timestamp::Percentage(10.0);
         let start = Timestamp::Start;
 
-        let result = percentage.is_before(&start, 100.0);
-        assert_eq!(result, false);
+        let result = percentage
+            .is_before(&start, &TimestampContext::with_length(100.0))
+            .unwrap();
+        assert!(!result);
 
-        let result = start.is_before(&percentage, 100.0);
-        assert_eq!(result, true);
+        let result = start
+            .is_before(&percentage, &TimestampContext::with_length(100.0))
+            .unwrap();
+        assert!(result);
     }
 
     #[test]
     fn is_before_percentage_is_before_end() {
-        let percentage = Timestamp::Percentage(10);
+        let percentage = Timestamp::Percentage(10.0);
         let end = Timestamp::End;
 
-        let result = percentage.is_before(&end, 100.0);
-        assert_eq!(result, true);
+        let result = percentage
+            .is_before(&end, &TimestampContext::with_length(100.0))
+            .unwrap();
+        assert!(result);
 
-        let result = end.is_before(&percentage, 100.0);
-        assert_eq!(result, false);
+        let result = end
+            .is_before(&percentage, &TimestampContext::with_length(100.0))
+            .unwrap();
+        assert!(!result);
     }
 
     #[test]
     fn is_before_percentage_is_before_seconds() {
-        let percentage = Timestamp::Percentage(5);
+        let percentage = Timestamp::Percentage(5.0);
         let seconds = Timestamp::Seconds(10.0);
 
-        let result = percentage.is_before(&seconds, 100.0);
-        assert_eq!(result, true);
+        let result = percentage
+            .is_before(&seconds, &TimestampContext::with_length(100.0))
+            .unwrap();
+        assert!(result);
 
-        let result = seconds.is_before(&percentage, 100.0);
-        assert_eq!(result, false);
+        let result = seconds
+            .is_before(&percentage, &TimestampContext::with_length(100.0))
+            .unwrap();
+        assert!(!result);
     }
 
     #[test]
     fn is_before_seconds_is_before_percentage() {
-        let percentage = Timestamp::Percentage(20);
+        let percentage = Timestamp::Percentage(20.0);
         let seconds = Timestamp::Seconds(10.0);
 
-        let result = seconds.is_before(&percentage, 100.0);
-        assert_eq!(result, true);
+        let result = seconds
+            .is_before(&percentage, &TimestampContext::with_length(100.0))
+            .unwrap();
+        assert!(result);
+
+        let result = percentage
+            .is_before(&seconds, &TimestampContext::with_length(100.0))
+            .unwrap();
+        assert!(!result);
+    }
+
+    #[test]
+    fn is_before_fractional_percentages_are_compared_precisely() {
+        let lower = Timestamp::Percentage(33.3);
+        let higher = Timestamp::Percentage(33.4);
+
+        let result = lower
+            .is_before(&higher, &TimestampContext::with_length(10800.0))
+            .unwrap();
+        assert!(result);
+
+        let result = higher
+            .is_before(&lower, &TimestampContext::with_length(10800.0))
+            .unwrap();
+        assert!(!result);
+    }
+
+    #[test]
+    fn is_before_frame_is_compared_using_frame_rate() {
+        let frame = Timestamp::Frame(250);
+        let seconds = Timestamp::Seconds(11.0);
+
+        let result = frame
+            .is_before(&seconds, &TimestampContext::with_length(100.0))
+            .unwrap();
+        assert!(result);
+
+        let result = seconds
+            .is_before(&frame, &TimestampContext::with_length(100.0))
+            .unwrap();
+        assert!(!result);
+    }
+
+    #[test]
+    fn is_before_from_end_is_after_start() {
+        let from_end = Timestamp::parse_timestamp("-30").unwrap();
+        let start = Timestamp::Start;
+
+        let result = start
+            .is_before(&from_end, &TimestampContext::with_length(100.0))
+            .unwrap();
+        assert!(result);
+
+        let result = from_end
+            .is_before(&start, &TimestampContext::with_length(100.0))
+            .unwrap();
+        assert!(!result);
+    }
+
+    #[test]
+    fn is_before_from_end_is_before_end() {
+        let from_end = Timestamp::parse_timestamp("-0:10").unwrap();
+        let end = Timestamp::End;
+
+        let result = from_end
+            .is_before(&end, &TimestampContext::with_length(100.0))
+            .unwrap();
+        assert!(result);
+
+        let result = end
+            .is_before(&from_end, &TimestampContext::with_length(100.0))
+            .unwrap();
+        assert!(!result);
+    }
+
+    #[test]
+    fn is_before_from_end_is_compared_against_seconds() {
+        let from_end = Timestamp::parse_timestamp("-30").unwrap();
+        let seconds = Timestamp::Seconds(60.0);
+
+        let result = seconds
+            .is_before(&from_end, &TimestampContext::with_length(100.0))
+            .unwrap();
+        assert!(result);
+
+        let result = from_end
+            .is_before(&seconds, &TimestampContext::with_length(100.0))
+            .unwrap();
+        assert!(!result);
+    }
+
+    #[test]
+    fn is_before_from_end_is_compared_against_from_end() {
+        let further_from_end = Timestamp::parse_timestamp("-30").unwrap();
+        let closer_to_end = Timestamp::parse_timestamp("-10").unwrap();
+
+        let result = further_from_end
+            .is_before(&closer_to_end, &TimestampContext::with_length(100.0))
+            .unwrap();
+        assert!(result);
+
+        let result = closer_to_end
+            .is_before(&further_from_end, &TimestampContext::with_length(100.0))
+            .unwrap();
+        assert!(!result);
+    }
+
+    // to_seconds tests
+    #[test]
+    fn to_seconds_works_with_fractional_percentage() {
+        let percentage = Timestamp::Percentage(12.5);
+        let result = percentage.to_seconds(&TimestampContext::with_length(200.0));
+        assert_eq!(result, Ok(25.0));
+    }
+
+    #[test]
+    fn to_seconds_counts_back_from_end() {
+        let from_end = Timestamp::parse_timestamp("-30").unwrap();
+        let result = from_end.to_seconds(&TimestampContext::with_length(100.0));
+        assert_eq!(result, Ok(70.0));
+    }
+
+    #[test]
+    fn to_seconds_fails_counting_back_past_start() {
+        let from_end = Timestamp::parse_timestamp("-500").unwrap();
+        let result = from_end.to_seconds(&TimestampContext::with_length(400.0));
+        assert!(matches!(result, Err(Error::InvalidTime(_))));
+    }
+
+    #[test]
+    fn to_seconds_converts_frames_with_fractional_frame_rate() {
+        let frame = Timestamp::Frame(30000);
+        let context = TimestampContext {
+            video_length: 2000.0,
+            frame_rate: Some(30000.0 / 1001.0),
+            ..Default::default()
+        };
+
+        let result = frame.to_seconds(&context);
+        assert_eq!(result, Ok(1001.0));
+    }
+
+    #[test]
+    fn to_seconds_fails_for_frames_without_frame_rate() {
+        let frame = Timestamp::Frame(100);
+        let context = TimestampContext {
+            video_length: 100.0,
+            frame_rate: None,
+            ..Default::default()
+        };
+
+        let result = frame.to_seconds(&context);
+        assert_eq!(result, Err(Error::UnknownFrameRate));
+    }
+
+    #[test]
+    fn to_seconds_resolves_timecode_relative_to_start_timecode() {
+        let timecode = Timestamp::parse_timestamp("01:00:10:12").unwrap();
+        let context = TimestampContext {
+            video_length: 100.0,
+            frame_rate: Some(25.0),
+            start_timecode: Some(Timecode::parse_timecode("01:00:00:00").unwrap()),
+            ..Default::default()
+        };
+
+        let result = timecode.to_seconds(&context);
+        assert_eq!(result, Ok(10.48));
+    }
+
+    #[test]
+    fn to_seconds_fails_for_timecode_before_start_timecode() {
+        let timecode = Timestamp::parse_timestamp("00:59:59:00").unwrap();
+        let context = TimestampContext {
+            video_length: 100.0,
+            frame_rate: Some(25.0),
+            start_timecode: Some(Timecode::parse_timecode("01:00:00:00").unwrap()),
+            ..Default::default()
+        };
+
+        let result = timecode.to_seconds(&context);
+        let expected = Err(Error::InvalidTime(
+            "Provided timecode is before the start timecode of the video".to_owned(),
+        ));
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn to_seconds_resolves_relative_from_relative_start() {
+        let relative = Timestamp::parse_timestamp("+45").unwrap();
+        let context = TimestampContext::with_length(1000.0).relative_to(750.0);
+
+        let result = relative.to_seconds(&context);
+        assert_eq!(result, Ok(795.0));
+    }
+
+    #[test]
+    fn is_before_relative_is_after_start() {
+        let start = Timestamp::Seconds(750.0);
+        let relative = Timestamp::parse_timestamp("+45").unwrap();
+        let context = TimestampContext::with_length(1000.0).relative_to(750.0);
+
+        let result = start.is_before(&relative, &context).unwrap();
+        assert!(result);
+
+        let result = relative.is_before(&start, &context).unwrap();
+        assert!(!result);
+    }
+
+    #[test]
+    fn to_seconds_resolves_chapter_boundaries() {
+        let context = TimestampContext {
+            video_length: 300.0,
+            chapters: vec![
+                Chapter {
+                    title: Some("Intro".to_owned()),
+                    start: 0.0,
+                    end: 60.0,
+                },
+                Chapter {
+                    title: Some("Main".to_owned()),
+                    start: 60.0,
+                    end: 300.0,
+                },
+            ],
+            ..Default::default()
+        };
+
+        let by_index = Timestamp::ChapterStart(ChapterRef::parse_chapter_ref("2"));
+        assert_eq!(by_index.to_seconds(&context), Ok(60.0));
+
+        let by_title = Timestamp::ChapterEnd(ChapterRef::parse_chapter_ref("intro"));
+        assert_eq!(by_title.to_seconds(&context), Ok(60.0));
+    }
+
+    #[test]
+    fn to_seconds_fails_with_missing_chapter() {
+        let context = TimestampContext::with_length(100.0);
+
+        let by_index = Timestamp::ChapterStart(ChapterRef::parse_chapter_ref("0"));
+        let expected = Err(Error::ChapterNotFound("0".to_owned()));
+        assert_eq!(by_index.to_seconds(&context), expected);
+
+        let by_title = Timestamp::ChapterEnd(ChapterRef::parse_chapter_ref("Outro"));
+        let expected = Err(Error::ChapterNotFound("Outro".to_owned()));
+        assert_eq!(by_title.to_seconds(&context), expected);
+    }
+
+    // format_seconds tests
+    #[test]
+    fn format_seconds_formats_as_hh_mm_ss_mmm() {
+        let result = format_seconds(hh_mm_ss_to_seconds(1, 2, 3) + 0.456);
+        assert_eq!(result, "01:02:03.456");
+    }
+
+    #[test]
+    fn format_seconds_can_be_parsed_back() {
+        let result = Timestamp::parse_timestamp(&format_seconds(754.25));
+        assert_eq!(result, Ok(Timestamp::Seconds(754.25)));
+    }
+
+    // Timecode tests
+    #[test]
+    fn parse_timecode_works_with_non_drop_frame() {
+        let result = Timecode::parse_timecode("01:02:03:04");
+        let expected = Ok(Timecode {
+            hours: 1,
+            minutes: 2,
+            seconds: 3,
+            frames: 4,
+            drop_frame: false,
+        });
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn parse_timecode_works_with_drop_frame() {
+        let result = Timecode::parse_timecode("01:02:03;04");
+        let expected = Ok(Timecode {
+            hours: 1,
+            minutes: 2,
+            seconds: 3,
+            frames: 4,
+            drop_frame: true,
+        });
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn to_frame_count_works_with_non_drop_frame() {
+        let timecode = Timecode::parse_timecode("00:01:00:00").unwrap();
+        let result = timecode.to_frame_count(30000.0 / 1001.0);
+        assert_eq!(result, Ok(1800));
+    }
 
-        let result = percentage.is_before(&seconds, 100.0);
-        assert_eq!(result, false);
+    #[test]
+    fn to_frame_count_skips_dropped_frames() {
+        let timecode = Timecode::parse_timecode("00:01:00;02").unwrap();
+        let result = timecode.to_frame_count(30000.0 / 1001.0);
+        assert_eq!(result, Ok(1800));
+    }
+
+    #[test]
+    fn to_frame_count_does_not_drop_frames_on_tenth_minute() {
+        let timecode = Timecode::parse_timecode("00:10:00;00").unwrap();
+        let result = timecode.to_frame_count(30000.0 / 1001.0);
+        assert_eq!(result, Ok(17982));
+    }
+
+    #[test]
+    fn to_frame_count_fails_with_dropped_frame_number() {
+        let timecode = Timecode::parse_timecode("00:01:00;01").unwrap();
+        let result = timecode.to_frame_count(30000.0 / 1001.0);
+        let expected = Err(Error::InvalidTime(
+            "Provided drop-frame timecode uses a frame number which is dropped".to_owned(),
+        ));
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn to_frame_count_fails_with_frames_over_frame_rate() {
+        let timecode = Timecode::parse_timecode("00:00:01:25").unwrap();
+        let result = timecode.to_frame_count(25.0);
+        let expected = Err(Error::InvalidTime(
+            "Provided value for frames must be less than the frame rate of 25".to_owned(),
+        ));
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn to_frame_count_fails_with_drop_frame_at_25_fps() {
+        let timecode = Timecode::parse_timecode("00:01:00;02").unwrap();
+        let result = timecode.to_frame_count(25.0);
+        let expected = Err(Error::InvalidTime(
+            "Provided drop-frame timecode can only be used with 29.97 or 59.94 fps video, not 25.000 fps"
+                .to_owned(),
+        ));
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn to_frame_count_fails_with_drop_frame_at_24_fps() {
+        let timecode = Timecode::parse_timecode("00:01:00;02").unwrap();
+        let result = timecode.to_frame_count(24000.0 / 1001.0);
+        let expected = Err(Error::InvalidTime(
+            "Provided drop-frame timecode can only be used with 29.97 or 59.94 fps video, not 23.976 fps"
+                .to_owned(),
+        ));
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn to_frame_count_skips_dropped_frames_at_59_94_fps() {
+        let timecode = Timecode::parse_timecode("00:01:00;04").unwrap();
+        let result = timecode.to_frame_count(60000.0 / 1001.0);
+        assert_eq!(result, Ok(3600));
+    }
+
+    // Snap tests
+    #[test]
+    fn snap_prev_keyframe_moves_back() {
+        let keyframes = [0.0, 2.0, 4.0, 6.0];
+        let result = Snap::PrevKeyframe.snap_seconds(3.5, &keyframes);
+        assert_eq!(result, 2.0);
+    }
+
+    #[test]
+    fn snap_next_keyframe_moves_forward() {
+        let keyframes = [0.0, 2.0, 4.0, 6.0];
+        let result = Snap::NextKeyframe.snap_seconds(2.5, &keyframes);
+        assert_eq!(result, 4.0);
+    }
+
+    #[test]
+    fn snap_nearest_picks_closest_keyframe() {
+        let keyframes = [0.0, 2.0, 4.0, 6.0];
+        assert_eq!(Snap::Nearest.snap_seconds(2.9, &keyframes), 2.0);
+        assert_eq!(Snap::Nearest.snap_seconds(3.1, &keyframes), 4.0);
+    }
+
+    #[test]
+    fn snap_keeps_timestamp_already_on_keyframe() {
+        let keyframes = [0.0, 2.0, 4.0, 6.0];
+        assert_eq!(Snap::PrevKeyframe.snap_seconds(4.0, &keyframes), 4.0);
+        assert_eq!(Snap::NextKeyframe.snap_seconds(4.0, &keyframes), 4.0);
+        assert_eq!(Snap::Nearest.snap_seconds(4.0, &keyframes), 4.0);
+    }
+
+    #[test]
+    fn snap_falls_back_to_closest_keyframe_past_the_ends() {
+        let keyframes = [2.0, 4.0];
+        assert_eq!(Snap::PrevKeyframe.snap_seconds(1.0, &keyframes), 2.0);
+        assert_eq!(Snap::NextKeyframe.snap_seconds(5.0, &keyframes), 4.0);
+    }
+
+    #[test]
+    fn snap_without_keyframes_keeps_timestamp() {
+        let result = Snap::Nearest.snap_seconds(3.0, &[]);
+        assert_eq!(result, 3.0);
     }
 }
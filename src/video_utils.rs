@@ -1,10 +1,18 @@
 use crate::error::Error;
+use crate::media_info::{MediaInfo, Stream};
+use crate::range;
+use crate::timestamp::Snap;
 
 use chrono::prelude::*;
+use std::cell::{Cell, OnceCell};
 use std::env;
-use std::path::PathBuf;
-use std::process::Command;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
 use std::str;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 
 pub fn check_program_installed(program_name: String) -> bool {
     if env::consts::OS == "windows" {
@@ -23,24 +31,38 @@ pub fn check_program_installed(program_name: String) -> bool {
     }
 }
 
-// Need to make this handle errors
-pub fn get_video_length(path: &PathBuf) -> Result<f64, Error> {
+/// Parses a frame rate in the fractional form ffprobe reports it in, e.g. `30000/1001` or `25/1`.
+pub fn parse_frame_rate(frame_rate: &str) -> Option<f64> {
+    let (numerator, denominator) = frame_rate.trim().split_once('/')?;
+    let numerator: f64 = numerator.parse().ok()?;
+    let denominator: f64 = denominator.parse().ok()?;
+
+    if numerator > 0.0 && denominator > 0.0 {
+        Some(numerator / denominator)
+    } else {
+        None
+    }
+}
+
+/// Returns the timestamps in seconds of every keyframe in the first video stream, in order.
+/// The timestamps are relative to `start_time`, the start time of the container, so that they
+/// line up with ffmpeg's `-ss` and every other timestamp.
+pub fn get_keyframes(path: &Path, start_time: f64) -> Result<Vec<f64>, Error> {
     if !check_program_installed("ffprobe".to_owned()) {
         Err(Error::FfprobeNotInstalled)?
     }
 
-    let video_path = path
-        .to_str()
-        .ok_or(Error::InvalidPath(path.to_string_lossy().into_owned()))?;
-
+    // Reading the packet flags avoids having to decode the video, which keeps this fast for long files
     let output = Command::new("ffprobe")
         .arg("-v")
         .arg("error")
+        .arg("-select_streams")
+        .arg("v:0")
         .arg("-show_entries")
-        .arg("format=duration")
+        .arg("packet=pts_time,flags")
         .arg("-of")
-        .arg("default=noprint_wrappers=1:nokey=1")
-        .arg(&video_path)
+        .arg("csv=print_section=0")
+        .arg(path)
         .output();
 
     let output = match output {
@@ -51,58 +73,567 @@ pub fn get_video_length(path: &PathBuf) -> Result<f64, Error> {
         Err(_) => return Err(Error::FfpmegError)?,
     };
 
-    let duration_str = str::from_utf8(&output.stdout).map_err(|_| {
+    let packets = str::from_utf8(&output.stdout).map_err(|_| {
         Error::InvalidData("Failed to read ffprobe output to a UTF-8 string".to_owned())
     })?;
 
-    let duration: f64 = duration_str.trim().parse().map_err(|_| {
-        Error::InvalidData(format!(
-            "Failed to parse ffprobe output as a float: {}",
-            duration_str
+    Ok(parse_keyframes(packets, start_time))
+}
+
+/// Parses the `pts_time,flags` lines written by ffprobe for each packet.
+fn parse_keyframes(packets: &str, start_time: f64) -> Vec<f64> {
+    let mut keyframes = packets
+        .lines()
+        .filter_map(|line| line.split_once(','))
+        .filter(|(_, flags)| flags.contains('K'))
+        .filter_map(|(pts_time, _)| pts_time.parse::<f64>().ok())
+        .map(|pts_time| pts_time - start_time)
+        .collect::<Vec<_>>();
+
+    keyframes.sort_by(|a, b| a.total_cmp(b));
+    keyframes
+}
+
+/// Moves the segments onto the keyframes of the video. Returns an error if nothing is left
+/// after snapping.
+pub fn snap_to_keyframes(
+    segments: &[(f64, f64)],
+    snap: Snap,
+    input: &TrimInput,
+    video_length: f64,
+) -> Result<Vec<(f64, f64)>, Error> {
+    let keyframes = input.keyframes()?;
+    let snapped_segments = range::snap_segments(segments, snap, keyframes, video_length);
+
+    if snapped_segments.is_empty() {
+        Err(Error::NothingToKeepAfterSnapping)?
+    }
+
+    Ok(snapped_segments)
+}
+
+/// The video being trimmed. Its keyframes are only read if a cut needs them, and then only once,
+/// as reading them means scanning every packet of the video.
+#[derive(Debug)]
+pub struct TrimInput<'a> {
+    pub path: &'a Path,
+    pub media_info: &'a MediaInfo,
+    keyframes: OnceCell<Vec<f64>>,
+}
+
+impl<'a> TrimInput<'a> {
+    pub fn new(path: &'a Path, media_info: &'a MediaInfo) -> TrimInput<'a> {
+        TrimInput {
+            path,
+            media_info,
+            keyframes: OnceCell::new(),
+        }
+    }
+
+    pub fn keyframes(&self) -> Result<&[f64], Error> {
+        if let Some(keyframes) = self.keyframes.get() {
+            return Ok(keyframes);
+        }
+
+        let keyframes = get_keyframes(self.path, self.media_info.start_time())?;
+        Ok(self.keyframes.get_or_init(|| keyframes))
+    }
+}
+
+/// How the video should be cut.
+#[derive(Debug, Clone)]
+pub enum TrimMode {
+    /// Copy the streams without re-encoding, cuts will snap to the nearest keyframe.
+    Copy,
+    /// Re-encode the video so that the cuts land exactly on the requested timestamps.
+    Accurate(EncodeOptions),
+    /// Only re-encode the partial groups of pictures at either end of the cut, and copy the
+    /// streams in between. Frame accurate like [`TrimMode::Accurate`] but close to the speed of
+    /// [`TrimMode::Copy`]. The re-encoded ends match the profile, pixel format and timebase of
+    /// the input video, and use an encoder for its codec unless one is provided.
+    SmartCut(EncodeOptions),
+}
+
+#[derive(Debug, Clone)]
+pub struct EncodeOptions {
+    /// The encoder to use, `libx264` when re-encoding the whole cut if not provided.
+    pub codec: Option<String>,
+    pub crf: u8,
+    pub preset: String,
+}
+
+/// How far through a trim ffmpeg has got, in seconds of the output video.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progress {
+    pub done: f64,
+    pub total: f64,
+    /// How many seconds of video ffmpeg is getting through each second, if it has reported it.
+    pub speed: Option<f64>,
+    pub elapsed: Duration,
+}
+
+impl Progress {
+    /// How much of the trim is done, from 0 to 1.
+    pub fn fraction(&self) -> f64 {
+        if self.total > 0.0 {
+            (self.done / self.total).clamp(0.0, 1.0)
+        } else {
+            1.0
+        }
+    }
+
+    /// An estimate of how long is left, based on how long the trim has taken so far.
+    pub fn eta(&self) -> Option<Duration> {
+        if self.done <= 0.0 {
+            return None;
+        }
+        let remaining = (self.total - self.done).max(0.0);
+        Some(Duration::from_secs_f64(
+            self.elapsed.as_secs_f64() * remaining / self.done,
         ))
-    })?;
+    }
+}
+
+/// Keeps track of the progress of a trim across every ffmpeg command it runs.
+pub struct ProgressTracker<'a> {
+    total: f64,
+    completed: Cell<f64>,
+    started: Instant,
+    on_progress: &'a dyn Fn(Progress),
+}
+
+impl<'a> ProgressTracker<'a> {
+    /// Tracks a trim which will write `total` seconds of video, calling `on_progress` as ffmpeg reports it.
+    pub fn new(total: f64, on_progress: &'a dyn Fn(Progress)) -> ProgressTracker<'a> {
+        ProgressTracker {
+            total,
+            completed: Cell::new(0.0),
+            started: Instant::now(),
+            on_progress,
+        }
+    }
 
-    Ok(duration)
+    /// Reports progress through the piece of the trim currently being written.
+    fn report(&self, piece_done: f64, speed: Option<f64>) {
+        (self.on_progress)(Progress {
+            done: self.completed.get() + piece_done,
+            total: self.total,
+            speed,
+            elapsed: self.started.elapsed(),
+        })
+    }
+
+    fn complete_piece(&self, piece_length: f64) {
+        self.completed.set(self.completed.get() + piece_length);
+    }
 }
 
-// Todo: Implement this function
+// Margin used when comparing timestamps against keyframes, as ffprobe reports them rounded
+const KEYFRAME_EPSILON: f64 = 0.001;
+
 pub fn trim_video(
     start: f64,
     end: f64,
-    input_path: &PathBuf,
-    output_path: &PathBuf,
+    input: &TrimInput,
+    output_path: &Path,
+    mode: &TrimMode,
+    progress: &ProgressTracker,
 ) -> Result<(), Error> {
     if !check_program_installed("ffmpeg".to_owned()) {
         Err(Error::FfpmegNotInstalled)?
     }
 
-    let output = Command::new("ffmpeg")
-        .arg("-i")
-        .arg(input_path)
-        .arg("-v")
-        .arg("error")
-        .arg("-ss")
-        .arg(format!("{}", start))
-        .arg("-to")
-        .arg(format!("{}", end))
-        .arg("-c")
-        .arg("copy")
-        .arg(output_path)
-        .output();
+    match mode {
+        TrimMode::Copy => copy_trim(start, end, input.path, output_path, &[], progress),
+        TrimMode::Accurate(options) => encode_trim(
+            start,
+            end,
+            input.path,
+            output_path,
+            &encode_args(options),
+            progress,
+        ),
+        TrimMode::SmartCut(options) => {
+            smart_cut_trim(start, end, input, output_path, options, progress)
+        }
+    }
+}
+
+fn copy_trim(
+    start: f64,
+    end: f64,
+    input_path: &Path,
+    output_path: &Path,
+    extra_args: &[String],
+    progress: &ProgressTracker,
+) -> Result<(), Error> {
+    run_ffmpeg_with_progress(
+        Command::new("ffmpeg")
+            .arg("-i")
+            .arg(input_path)
+            .arg("-v")
+            .arg("error")
+            .arg("-ss")
+            .arg(format!("{}", start))
+            .arg("-to")
+            .arg(format!("{}", end))
+            .arg("-c")
+            .arg("copy")
+            .args(extra_args)
+            .arg(output_path),
+        end - start,
+        progress,
+    )
+}
+
+/// Re-encodes the video between `start` and `end` with the given video encoder arguments,
+/// copying the other streams.
+fn encode_trim(
+    start: f64,
+    end: f64,
+    input_path: &Path,
+    output_path: &Path,
+    video_args: &[String],
+    progress: &ProgressTracker,
+) -> Result<(), Error> {
+    // Seeking on the input is frame accurate when re-encoding, and much faster than
+    // decoding everything up to the start point.
+    run_ffmpeg_with_progress(
+        Command::new("ffmpeg")
+            .arg("-v")
+            .arg("error")
+            .arg("-ss")
+            .arg(format!("{}", start))
+            .arg("-i")
+            .arg(input_path)
+            .arg("-t")
+            .arg(format!("{}", end - start))
+            .args(video_args)
+            .arg("-c:a")
+            .arg("copy")
+            .arg(output_path),
+        end - start,
+        progress,
+    )
+}
+
+fn smart_cut_trim(
+    start: f64,
+    end: f64,
+    input: &TrimInput,
+    output_path: &Path,
+    options: &EncodeOptions,
+    progress: &ProgressTracker,
+) -> Result<(), Error> {
+    let extension = get_extension(input.path);
+    let video_args = smart_cut_video_args(
+        input.media_info.video_stream(),
+        options,
+        &extension.to_ascii_lowercase(),
+    )?;
+
+    // The concat demuxer only keeps the parameter sets of the first piece, so every piece
+    // carries its own in front of each keyframe for the pieces after it to decode correctly
+    let copy_args = ["-bsf:v".to_owned(), "dump_extra=freq=keyframe".to_owned()];
+    let encode_args = [video_args.as_slice(), &copy_args].concat();
+
+    let trim_piece = |start, end, piece, piece_path: &Path| match piece {
+        SmartCutPiece::Copy => copy_trim(start, end, input.path, piece_path, &copy_args, progress),
+        SmartCutPiece::Encode => {
+            encode_trim(start, end, input.path, piece_path, &encode_args, progress)
+        }
+    };
+
+    let pieces = plan_smart_cut(start, end, input.keyframes()?);
+
+    if let [(start, end, piece)] = pieces.as_slice() {
+        return trim_piece(*start, *end, *piece, output_path);
+    }
+
+    with_temp_dir(|temp_dir| {
+        let mut piece_paths = Vec::new();
+        for (index, (start, end, piece)) in pieces.iter().enumerate() {
+            let piece_path = temp_dir.join(format!("piece_{}.{}", index, extension));
+            trim_piece(*start, *end, *piece, &piece_path)?;
+            piece_paths.push(piece_path);
+        }
+
+        concat_videos(&piece_paths, output_path, temp_dir)
+    })
+}
+
+/// Whether a piece of a smart cut is re-encoded or copied.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SmartCutPiece {
+    Encode,
+    Copy,
+}
+
+/// Splits a smart cut into the partial groups of pictures at either end, which are re-encoded,
+/// and the whole groups of pictures between the first and last keyframes, which are copied.
+fn plan_smart_cut(start: f64, end: f64, keyframes: &[f64]) -> Vec<(f64, f64, SmartCutPiece)> {
+    let first_keyframe = keyframes
+        .iter()
+        .find(|keyframe| **keyframe >= start - KEYFRAME_EPSILON);
+    let last_keyframe = keyframes
+        .iter()
+        .rev()
+        .find(|keyframe| **keyframe <= end + KEYFRAME_EPSILON);
+
+    // Without a whole group of pictures inside the cut there is nothing to copy
+    let (first_keyframe, last_keyframe) = match (first_keyframe, last_keyframe) {
+        (Some(first), Some(last)) if first < last => (*first, *last),
+        _ => return vec![(start, end, SmartCutPiece::Encode)],
+    };
+
+    let mut pieces = Vec::new();
+    if first_keyframe - start > KEYFRAME_EPSILON {
+        pieces.push((start, first_keyframe, SmartCutPiece::Encode));
+    }
+    pieces.push((first_keyframe, last_keyframe, SmartCutPiece::Copy));
+    if end - last_keyframe > KEYFRAME_EPSILON {
+        pieces.push((last_keyframe, end, SmartCutPiece::Encode));
+    }
+    pieces
+}
+
+/// The video encoder arguments for re-encoding a whole cut.
+fn encode_args(options: &EncodeOptions) -> Vec<String> {
+    vec![
+        "-c:v".to_owned(),
+        options.codec.as_deref().unwrap_or("libx264").to_owned(),
+        "-crf".to_owned(),
+        options.crf.to_string(),
+        "-preset".to_owned(),
+        options.preset.clone(),
+    ]
+}
+
+/// The video encoder arguments for the re-encoded ends of a smart cut. These have to match the
+/// input's video stream closely enough to be joined to its copied middle without re-encoding it,
+/// so the encoder is picked for the input's codec, and its profile, level, pixel format and
+/// timebase are kept.
+fn smart_cut_video_args(
+    stream: Option<&Stream>,
+    options: &EncodeOptions,
+    extension: &str,
+) -> Result<Vec<String>, Error> {
+    // Without a video stream there is only audio, which is copied anyway
+    let stream = match stream {
+        Some(stream) => stream,
+        None => return Ok(Vec::new()),
+    };
+
+    let codec = stream.codec_name.as_deref().unwrap_or("unknown");
+    let encoder = match (options.codec.as_deref(), codec) {
+        (Some(encoder), _) => encoder,
+        (None, "h264") => "libx264",
+        (None, "hevc") => "libx265",
+        (None, codec) => Err(Error::UnsupportedSmartCutCodec(codec.to_owned()))?,
+    };
+
+    let mut args = encode_args(&EncodeOptions {
+        codec: Some(encoder.to_owned()),
+        ..options.clone()
+    });
+
+    let profile = stream.profile.as_deref().map(str::to_ascii_lowercase);
+    let profile = match (encoder, codec, profile.as_deref()) {
+        ("libx264", "h264", Some("baseline" | "constrained baseline")) => Some("baseline"),
+        ("libx264", "h264", Some("main")) => Some("main"),
+        ("libx264", "h264", Some("high")) => Some("high"),
+        ("libx264", "h264", Some("high 10")) => Some("high10"),
+        ("libx264", "h264", Some("high 4:2:2")) => Some("high422"),
+        ("libx264", "h264", Some("high 4:4:4 predictive")) => Some("high444"),
+        ("libx265", "hevc", Some("main")) => Some("main"),
+        ("libx265", "hevc", Some("main 10")) => Some("main10"),
+        _ => None,
+    };
+    if let Some(profile) = profile {
+        args.extend(["-profile:v".to_owned(), profile.to_owned()]);
+    }
+
+    if let ("libx264", "h264", Some(level)) = (encoder, codec, stream.level) {
+        if level > 0 {
+            args.extend(["-level:v".to_owned(), level.to_string()]);
+        }
+    }
+
+    if let Some(pix_fmt) = &stream.pix_fmt {
+        args.extend(["-pix_fmt".to_owned(), pix_fmt.clone()]);
+    }
+
+    // Only the MP4 family of containers lets the timebase of the video track be chosen
+    let timescale = stream
+        .time_base
+        .as_deref()
+        .and_then(|time_base| time_base.strip_prefix("1/"));
+    if let (Some(timescale), "mp4" | "m4v" | "mov") = (timescale, extension) {
+        args.extend(["-video_track_timescale".to_owned(), timescale.to_owned()]);
+    }
 
-    match output {
+    Ok(args)
+}
+
+/// Trims each of the given segments out of the input video and joins them together
+/// into a single output file using ffmpeg's concat demuxer.
+pub fn trim_segments(
+    segments: &[(f64, f64)],
+    input: &TrimInput,
+    output_path: &Path,
+    mode: &TrimMode,
+    progress: &ProgressTracker,
+) -> Result<(), Error> {
+    if let [(start, end)] = segments {
+        return trim_video(*start, *end, input, output_path, mode, progress);
+    }
+
+    with_temp_dir(|temp_dir| {
+        let extension = get_extension(input.path);
+
+        let mut segment_paths = Vec::new();
+        for (index, (start, end)) in segments.iter().enumerate() {
+            let segment_path = temp_dir.join(format!("segment_{}.{}", index, extension));
+            trim_video(*start, *end, input, &segment_path, mode, progress)?;
+            segment_paths.push(segment_path);
+        }
+
+        concat_videos(&segment_paths, output_path, temp_dir)
+    })
+}
+
+fn concat_videos(
+    input_paths: &[PathBuf],
+    output_path: &Path,
+    temp_dir: &Path,
+) -> Result<(), Error> {
+    let list_path = temp_dir.join("segments.txt");
+    let list = input_paths
+        .iter()
+        .map(|path| format!("file '{}'", path.to_string_lossy().replace('\'', r"'\''")))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    fs::write(&list_path, list)
+        .map_err(|_| Error::InvalidPath(list_path.to_string_lossy().into_owned()))?;
+
+    run_ffmpeg(
+        Command::new("ffmpeg")
+            .arg("-v")
+            .arg("error")
+            .arg("-f")
+            .arg("concat")
+            .arg("-safe")
+            .arg("0")
+            .arg("-i")
+            .arg(&list_path)
+            .arg("-c")
+            .arg("copy")
+            .arg(output_path),
+    )
+}
+
+fn run_ffmpeg(command: &mut Command) -> Result<(), Error> {
+    match command.output() {
         Ok(output) => match output.status.success() {
-            true => output,
-            false => return Err(Error::FfpmegError)?,
+            true => Ok(()),
+            false => Err(Error::FfpmegError),
         },
-        Err(_) => return Err(Error::FfpmegError)?,
-    };
+        Err(_) => Err(Error::FfpmegError),
+    }
+}
+
+/// Runs ffmpeg, reading the progress it writes to stdout with `-progress pipe:1` as it goes.
+/// `length` is the number of seconds of video the command will write.
+fn run_ffmpeg_with_progress(
+    command: &mut Command,
+    length: f64,
+    progress: &ProgressTracker,
+) -> Result<(), Error> {
+    let mut child = command
+        .arg("-progress")
+        .arg("pipe:1")
+        .arg("-nostats")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|_| Error::FfpmegError)?;
+
+    if let Some(stdout) = child.stdout.take() {
+        let mut out_time = 0.0;
+        let mut speed = None;
+
+        // Each update is a block of `key=value` lines ending with a `progress` line
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            match line.split_once('=') {
+                Some(("out_time_ms", value)) => {
+                    out_time = parse_out_time_ms(value).unwrap_or(out_time)
+                }
+                Some(("speed", value)) => speed = parse_speed(value),
+                Some(("progress", _)) => progress.report(out_time.min(length), speed),
+                _ => {}
+            }
+        }
+    }
+
+    match child.wait() {
+        Ok(status) if status.success() => {
+            progress.complete_piece(length);
+            Ok(())
+        }
+        _ => Err(Error::FfpmegError),
+    }
+}
+
+/// Despite its name ffmpeg reports `out_time_ms` in microseconds, and as `N/A` before the first frame.
+fn parse_out_time_ms(out_time_ms: &str) -> Option<f64> {
+    out_time_ms
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .map(|microseconds| (microseconds / 1_000_000.0).max(0.0))
+}
+
+/// Parses a speed such as `1.53x`, which is `N/A` until ffmpeg has measured it.
+fn parse_speed(speed: &str) -> Option<f64> {
+    speed
+        .trim()
+        .strip_suffix('x')?
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|speed| *speed > 0.0)
+}
+
+/// Runs the given function with a fresh temporary directory, which is removed afterwards
+/// regardless of whether the function succeeded.
+fn with_temp_dir<F>(f: F) -> Result<(), Error>
+where
+    F: FnOnce(&Path) -> Result<(), Error>,
+{
+    static TEMP_DIR_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+    let temp_dir = env::temp_dir().join(format!(
+        "ttrim_{}_{}",
+        std::process::id(),
+        TEMP_DIR_COUNT.fetch_add(1, Ordering::SeqCst)
+    ));
+    fs::create_dir_all(&temp_dir)
+        .map_err(|_| Error::InvalidPath(temp_dir.to_string_lossy().into_owned()))?;
+
+    let result = f(&temp_dir);
+
+    let _ = fs::remove_dir_all(&temp_dir);
+    result
+}
 
-    Ok(())
+fn get_extension(path: &Path) -> &str {
+    path.extension().and_then(|s| s.to_str()).unwrap_or("mp4")
 }
 
 // TODO: More file formats are probably supported
-pub fn check_valid_file_extension(path: &PathBuf) -> bool {
+pub fn check_valid_file_extension(path: &Path) -> bool {
     let valid_extensions = [
         "mp4", "avi", "mov", "wmv", "flv", "mkv", "webm", "m4v", "mpg", "mpeg", "m2v", "3gp",
         "3g2", "m4v",
@@ -114,12 +645,91 @@ pub fn check_valid_file_extension(path: &PathBuf) -> bool {
     }
 }
 
+#[derive(Clone, Copy)]
 pub enum PathType {
     Relative,
     FileOnly,
 }
 
-pub fn generate_output_filename(input_path: &PathBuf, path_type: PathType) -> PathBuf {
+pub fn generate_output_filename(input_path: &Path, path_type: PathType) -> PathBuf {
+    generate_output_filename_with_suffix(input_path, path_type, "trim")
+}
+
+/// Generates an output filename for one of several numbered parts of the input video.
+/// The part number is zero padded so that the files sort in order.
+pub fn generate_numbered_output_filename(
+    input_path: &Path,
+    path_type: PathType,
+    part: usize,
+    total_parts: usize,
+) -> PathBuf {
+    let width = total_parts.to_string().len();
+    generate_output_filename_with_suffix(
+        input_path,
+        path_type,
+        &format!("part{:0width$}", part, width = width),
+    )
+}
+
+/// Generates an output filename for the trim in one row of a manifest. The row number is
+/// zero padded so that the files sort in the same order as the manifest.
+pub fn generate_row_output_filename(
+    input_path: &Path,
+    path_type: PathType,
+    row: usize,
+    total_rows: usize,
+) -> PathBuf {
+    let width = total_rows.to_string().len();
+    generate_output_filename_with_suffix(
+        input_path,
+        path_type,
+        &format!("row{:0width$}_trim", row, width = width),
+    )
+}
+
+/// Generates an output filename for one chapter of the input video, named after the chapter's
+/// title if it has one. The chapter number is zero padded so that the files sort in order.
+pub fn generate_chapter_output_filename(
+    input_path: &Path,
+    path_type: PathType,
+    chapter: usize,
+    total_chapters: usize,
+    title: Option<&str>,
+) -> PathBuf {
+    let width = total_chapters.to_string().len();
+    let suffix = match title
+        .map(sanitize_filename)
+        .filter(|title| !title.is_empty())
+    {
+        Some(title) => format!("{:0width$}_{}", chapter, title, width = width),
+        None => format!("chapter{:0width$}", chapter, width = width),
+    };
+    generate_output_filename_with_suffix(input_path, path_type, &suffix)
+}
+
+/// Makes a string safe to use as part of a filename on any platform, replacing reserved
+/// characters and whitespace with underscores.
+fn sanitize_filename(name: &str) -> String {
+    const MAX_LENGTH: usize = 100;
+
+    let sanitized = name
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() || c.is_whitespace() => '_',
+            c => c,
+        })
+        .take(MAX_LENGTH)
+        .collect::<String>();
+
+    sanitized.trim_matches(|c| c == '_' || c == '.').to_owned()
+}
+
+fn generate_output_filename_with_suffix(
+    input_path: &Path,
+    path_type: PathType,
+    suffix: &str,
+) -> PathBuf {
     let mut output_path = PathBuf::new();
 
     let filestem = input_path
@@ -139,6 +749,200 @@ pub fn generate_output_filename(input_path: &PathBuf, path_type: PathType) -> Pa
         output_path.push("./");
     }
 
-    output_path.push(format!("{}_{}_trim.{}", filestem, timestamp, extension));
+    output_path.push(format!(
+        "{}_{}_{}.{}",
+        filestem, timestamp, suffix, extension
+    ));
     output_path
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use SmartCutPiece::{Copy, Encode};
+
+    fn encode_options(codec: Option<&str>) -> EncodeOptions {
+        EncodeOptions {
+            codec: codec.map(str::to_owned),
+            crf: 18,
+            preset: "medium".to_owned(),
+        }
+    }
+
+    fn h264_stream() -> Stream {
+        Stream {
+            codec_type: Some("video".to_owned()),
+            codec_name: Some("h264".to_owned()),
+            profile: Some("High".to_owned()),
+            level: Some(40),
+            pix_fmt: Some("yuv420p".to_owned()),
+            time_base: Some("1/15360".to_owned()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn plan_smart_cut_encodes_both_ends() {
+        let result = plan_smart_cut(3.0, 17.0, &[0.0, 5.0, 10.0, 15.0, 20.0]);
+        let expected = vec![(3.0, 5.0, Encode), (5.0, 15.0, Copy), (15.0, 17.0, Encode)];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn plan_smart_cut_copies_from_start_on_keyframe() {
+        let result = plan_smart_cut(5.0, 17.0, &[0.0, 5.0, 10.0, 15.0, 20.0]);
+        let expected = vec![(5.0, 15.0, Copy), (15.0, 17.0, Encode)];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn plan_smart_cut_copies_up_to_end_on_keyframe() {
+        let result = plan_smart_cut(3.0, 15.0, &[0.0, 5.0, 10.0, 15.0, 20.0]);
+        let expected = vec![(3.0, 5.0, Encode), (5.0, 15.0, Copy)];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn plan_smart_cut_encodes_everything_without_keyframe_inside() {
+        let result = plan_smart_cut(6.0, 9.0, &[0.0, 5.0, 10.0]);
+        assert_eq!(result, vec![(6.0, 9.0, Encode)]);
+    }
+
+    #[test]
+    fn plan_smart_cut_encodes_everything_with_one_keyframe_inside() {
+        let result = plan_smart_cut(3.0, 7.0, &[0.0, 5.0, 10.0]);
+        assert_eq!(result, vec![(3.0, 7.0, Encode)]);
+    }
+
+    #[test]
+    fn smart_cut_video_args_match_input_stream() {
+        let result = smart_cut_video_args(Some(&h264_stream()), &encode_options(None), "mp4");
+        let expected = [
+            "-c:v",
+            "libx264",
+            "-crf",
+            "18",
+            "-preset",
+            "medium",
+            "-profile:v",
+            "high",
+            "-level:v",
+            "40",
+            "-pix_fmt",
+            "yuv420p",
+            "-video_track_timescale",
+            "15360",
+        ]
+        .map(str::to_owned)
+        .to_vec();
+        assert_eq!(result, Ok(expected));
+    }
+
+    #[test]
+    fn smart_cut_video_args_use_provided_encoder() {
+        let result = smart_cut_video_args(
+            Some(&h264_stream()),
+            &encode_options(Some("h264_nvenc")),
+            "mkv",
+        )
+        .unwrap();
+        assert_eq!(&result[..2], ["-c:v", "h264_nvenc"]);
+        assert!(!result.contains(&"-profile:v".to_owned()));
+        assert!(!result.contains(&"-video_track_timescale".to_owned()));
+    }
+
+    #[test]
+    fn smart_cut_video_args_fail_without_encoder_for_codec() {
+        let stream = Stream {
+            codec_name: Some("prores".to_owned()),
+            ..h264_stream()
+        };
+        let result = smart_cut_video_args(Some(&stream), &encode_options(None), "mov");
+        assert_eq!(
+            result,
+            Err(Error::UnsupportedSmartCutCodec("prores".to_owned()))
+        );
+    }
+
+    #[test]
+    fn parse_keyframes_offsets_by_start_time() {
+        let packets = "1.400000,K__\n1.440000,___\n3.400000,K__\n2.400000,K__\n";
+        let result = parse_keyframes(packets, 1.4);
+        let expected = [0.0, 1.0, 2.0];
+        assert_eq!(result.len(), expected.len());
+        for (keyframe, expected) in result.iter().zip(expected) {
+            assert!((keyframe - expected).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn parse_out_time_ms_reads_microseconds() {
+        assert_eq!(parse_out_time_ms("12500000"), Some(12.5));
+        assert_eq!(parse_out_time_ms("N/A"), None);
+    }
+
+    #[test]
+    fn parse_speed_reads_multiplier() {
+        assert_eq!(parse_speed(" 1.53x"), Some(1.53));
+        assert_eq!(parse_speed("N/A"), None);
+    }
+
+    #[test]
+    fn progress_estimates_time_left() {
+        let progress = Progress {
+            done: 30.0,
+            total: 120.0,
+            speed: Some(2.0),
+            elapsed: Duration::from_secs(15),
+        };
+        assert_eq!(progress.fraction(), 0.25);
+        assert_eq!(progress.eta(), Some(Duration::from_secs(45)));
+    }
+
+    #[test]
+    fn progress_tracker_adds_up_pieces() {
+        let reported = std::cell::RefCell::new(Vec::new());
+        let on_progress = |progress: Progress| reported.borrow_mut().push(progress.done);
+        let tracker = ProgressTracker::new(30.0, &on_progress);
+
+        tracker.report(5.0, None);
+        tracker.complete_piece(10.0);
+        tracker.report(15.0, None);
+
+        assert_eq!(*reported.borrow(), vec![5.0, 25.0]);
+    }
+
+    #[test]
+    fn parse_frame_rate_works_with_fractional_rate() {
+        let result = parse_frame_rate("30000/1001");
+        assert_eq!(result, Some(30000.0 / 1001.0));
+    }
+
+    #[test]
+    fn parse_frame_rate_fails_with_unknown_rate() {
+        let result = parse_frame_rate("0/0");
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn sanitize_filename_replaces_reserved_characters() {
+        let result = sanitize_filename("Part 1: Intro/Setup?");
+        assert_eq!(result, "Part_1__Intro_Setup");
+    }
+
+    #[test]
+    fn sanitize_filename_trims_leading_dots() {
+        let result = sanitize_filename("..hidden");
+        assert_eq!(result, "hidden");
+    }
+
+    #[test]
+    fn generate_chapter_output_filename_falls_back_to_chapter_number() {
+        let input = Path::new("lecture.mkv");
+        let result = generate_chapter_output_filename(input, PathType::FileOnly, 3, 12, Some("??"));
+        let filename = result.to_string_lossy();
+        assert!(filename.starts_with("lecture_"));
+        assert!(filename.ends_with("_chapter03.mkv"));
+    }
+}